@@ -14,6 +14,7 @@ mod override_layout;
 mod periph;
 mod periph_map;
 mod periph_singular;
+mod proc_loop;
 mod reg;
 mod reg_tokens;
 mod reg_tokens_inner;
@@ -21,6 +22,7 @@ mod simple_token;
 mod simple_tokens;
 mod static_tokens;
 mod stream;
+mod stream_record;
 mod thr_pool;
 mod thr_soft;
 
@@ -56,6 +58,11 @@ pub fn periph_singular(input: TokenStream) -> TokenStream {
     periph_singular::proc_macro(input)
 }
 
+#[proc_macro_attribute]
+pub fn proc_loop(args: TokenStream, input: TokenStream) -> TokenStream {
+    proc_loop::proc_macro(args, input)
+}
+
 #[proc_macro]
 pub fn reg(input: TokenStream) -> TokenStream {
     reg::proc_macro(input)
@@ -91,6 +98,11 @@ pub fn stream(input: TokenStream) -> TokenStream {
     stream::proc_macro(input)
 }
 
+#[proc_macro_derive(StreamRecord)]
+pub fn derive_stream_record(input: TokenStream) -> TokenStream {
+    stream_record::proc_macro_derive(input)
+}
+
 #[proc_macro]
 pub fn thr_pool(input: TokenStream) -> TokenStream {
     thr_pool::proc_macro(input)
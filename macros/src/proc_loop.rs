@@ -0,0 +1,367 @@
+use heck::ToUpperCamelCase;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream, Result};
+use syn::{
+    braced, parse_macro_input, parse_quote, Attribute, Block, FnArg, Ident, ImplItem, Pat, Path,
+    ReturnType, Signature, Token, Type, Visibility,
+};
+
+struct Input {
+    attrs: Vec<Attribute>,
+    trait_path: Path,
+    ident: Ident,
+    items: Vec<ImplItem>,
+    cmds: Vec<Method>,
+    reqs: Vec<Method>,
+}
+
+struct Method {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    sig: Signature,
+    block: Option<Block>,
+}
+
+struct Arg {
+    ident: Ident,
+    ty: Type,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        input.parse::<Token![impl]>()?;
+        let trait_path = input.parse()?;
+        input.parse::<Token![for]>()?;
+        let ident = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut items = Vec::new();
+        let mut cmds = Vec::new();
+        let mut reqs = Vec::new();
+        while !content.is_empty() {
+            let fork = content.fork();
+            let attrs = fork.call(Attribute::parse_outer)?;
+            if attrs.iter().any(|attr| attr.path.is_ident("cmd")) {
+                let method = content.parse::<Method>()?;
+                if method.block.is_none() {
+                    return Err(content.error("command must have a body"));
+                }
+                cmds.push(method);
+            } else if attrs.iter().any(|attr| attr.path.is_ident("req")) {
+                let method = content.parse::<Method>()?;
+                if method.block.is_some() {
+                    return Err(content.error("request must not have a body"));
+                }
+                reqs.push(method);
+            } else {
+                items.push(content.parse()?);
+            }
+        }
+        Ok(Self { attrs, trait_path, ident, items, cmds, reqs })
+    }
+}
+
+impl Parse for Method {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let attrs = input
+            .call(Attribute::parse_outer)?
+            .into_iter()
+            .filter(|attr| !attr.path.is_ident("cmd") && !attr.path.is_ident("req"))
+            .collect();
+        let vis = input.parse()?;
+        let sig = input.parse()?;
+        let block = if input.peek(Token![;]) {
+            input.parse::<Token![;]>()?;
+            None
+        } else {
+            Some(input.parse()?)
+        };
+        Ok(Self { attrs, vis, sig, block })
+    }
+}
+
+impl Method {
+    fn args(&self, skip: usize) -> Result<Vec<Arg>> {
+        self.sig
+            .inputs
+            .iter()
+            .skip(skip)
+            .map(|input| match input {
+                FnArg::Typed(arg) => match &*arg.pat {
+                    Pat::Ident(pat) => Ok(Arg { ident: pat.ident.clone(), ty: (*arg.ty).clone() }),
+                    pat => Err(syn::Error::new_spanned(pat, "expected an identifier")),
+                },
+                FnArg::Receiver(receiver) => {
+                    Err(syn::Error::new_spanned(receiver, "unexpected receiver"))
+                }
+            })
+            .collect()
+    }
+
+    fn output(&self) -> Type {
+        match &self.sig.output {
+            ReturnType::Default => parse_quote!(()),
+            ReturnType::Type(_, ty) => (**ty).clone(),
+        }
+    }
+
+    fn variant(&self) -> Ident {
+        format_ident!("{}", self.sig.ident.to_string().to_upper_camel_case())
+    }
+
+    fn docs(&self) -> Vec<&Attribute> {
+        self.attrs.iter().filter(|attr| attr.path.is_ident("doc")).collect()
+    }
+}
+
+pub fn proc_macro(args: TokenStream, input: TokenStream) -> TokenStream {
+    let vis = parse_macro_input!(args as Visibility);
+    let input = parse_macro_input!(input as Input);
+    match expand(&vis, &input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+fn expand(vis: &Visibility, input: &Input) -> Result<TokenStream2> {
+    let Input { attrs, trait_path, ident, items, cmds, reqs } = input;
+    let cmd_ident = format_ident!("{}Cmd", ident);
+    let cmd_res_ident = format_ident!("{}CmdRes", ident);
+    let req_ident = format_ident!("{}Req", ident);
+    let req_res_ident = format_ident!("{}ReqRes", ident);
+    let sess_ident = format_ident!("{}Sess", ident);
+    let context_ident = format_ident!("{}Context", ident);
+    let handler_ident = format_ident!("{}ReqHandler", ident);
+
+    let mut cmd_variants = Vec::new();
+    let mut cmd_res_fields = Vec::new();
+    let mut cmd_arms = Vec::new();
+    let mut cmd_fns = Vec::new();
+    let mut sess_decls = Vec::new();
+    let mut sess_fns = Vec::new();
+    for cmd in cmds {
+        let Method { attrs, vis: fn_vis, sig, block } = cmd;
+        if !matches!(sig.inputs.first(), Some(FnArg::Typed(_))) {
+            return Err(syn::Error::new_spanned(
+                sig,
+                "command must take the context token as the first argument",
+            ));
+        }
+        let args = cmd.args(1)?;
+        let arg_ident = args.iter().map(|arg| &arg.ident).collect::<Vec<_>>();
+        let arg_ty = args.iter().map(|arg| &arg.ty).collect::<Vec<_>>();
+        let output = cmd.output();
+        let variant = cmd.variant();
+        let fn_ident = &sig.ident;
+        let docs = cmd.docs();
+        let mut sig = sig.clone();
+        if let Some(FnArg::Typed(context)) = sig.inputs.first_mut() {
+            *context.ty = parse_quote!(<Self as ::drone_core::proc_loop::ProcLoop>::Context);
+        }
+        cmd_variants.push(quote! {
+            #(#docs)*
+            #variant(#(#arg_ty),*)
+        });
+        cmd_res_fields.push(quote! {
+            #fn_ident: ::core::mem::ManuallyDrop<#output>
+        });
+        cmd_arms.push(quote! {
+            #cmd_ident::#variant(#(#arg_ident),*) => #cmd_res_ident {
+                #fn_ident: ::core::mem::ManuallyDrop::new(
+                    Self::#fn_ident(context, #(#arg_ident),*),
+                ),
+            }
+        });
+        cmd_fns.push(quote! {
+            #(#attrs)*
+            #fn_vis #sig #block
+        });
+        sess_decls.push(quote! {
+            #(#docs)*
+            fn #fn_ident(
+                &mut self,
+                #(#arg_ident: #arg_ty),*
+            ) -> ::core::pin::Pin<::drone_core::_rt::alloc::boxed::Box<
+                dyn ::core::future::Future<
+                    Output = ::core::result::Result<#output, Self::Error>,
+                > + ::core::marker::Send + '_,
+            >>;
+        });
+        sess_fns.push(quote! {
+            fn #fn_ident(
+                &mut self,
+                #(#arg_ident: #arg_ty),*
+            ) -> ::core::pin::Pin<::drone_core::_rt::alloc::boxed::Box<
+                dyn ::core::future::Future<
+                    Output = ::core::result::Result<#output, Self::Error>,
+                > + ::core::marker::Send + '_,
+            >> {
+                let cmd = ::drone_core::proc_loop::Sess::cmd(
+                    self,
+                    #cmd_ident::#variant(#(#arg_ident),*),
+                );
+                ::drone_core::_rt::alloc::boxed::Box::pin(async move {
+                    cmd.await.map(|res| unsafe {
+                        ::core::mem::ManuallyDrop::into_inner(res.#fn_ident)
+                    })
+                })
+            }
+        });
+    }
+
+    let mut req_variants = Vec::new();
+    let mut req_res_fields = Vec::new();
+    let mut req_arms = Vec::new();
+    let mut context_decls = Vec::new();
+    let mut context_fns = Vec::new();
+    let mut handler_decls = Vec::new();
+    for req in reqs {
+        let args = req.args(0)?;
+        let arg_ident = args.iter().map(|arg| &arg.ident).collect::<Vec<_>>();
+        let arg_ty = args.iter().map(|arg| &arg.ty).collect::<Vec<_>>();
+        let output = req.output();
+        let variant = req.variant();
+        let fn_ident = &req.sig.ident;
+        let docs = req.docs();
+        req_variants.push(quote! {
+            #(#docs)*
+            #variant(#(#arg_ty),*)
+        });
+        req_res_fields.push(quote! {
+            #fn_ident: ::core::mem::ManuallyDrop<#output>
+        });
+        req_arms.push(quote! {
+            #req_ident::#variant(#(#arg_ident),*) => {
+                let res = #handler_ident::#fn_ident(handler, #(#arg_ident),*);
+                ::drone_core::_rt::alloc::boxed::Box::pin(async move {
+                    res.await.map(|res| #req_res_ident {
+                        #fn_ident: ::core::mem::ManuallyDrop::new(res),
+                    })
+                })
+            }
+        });
+        context_decls.push(quote! {
+            #(#docs)*
+            fn #fn_ident(self, #(#arg_ident: #arg_ty),*) -> #output;
+        });
+        context_fns.push(quote! {
+            fn #fn_ident(self, #(#arg_ident: #arg_ty),*) -> #output {
+                let res = ::drone_core::proc_loop::Context::req(
+                    self,
+                    #req_ident::#variant(#(#arg_ident),*),
+                );
+                unsafe { ::core::mem::ManuallyDrop::into_inner(res.#fn_ident) }
+            }
+        });
+        handler_decls.push(quote! {
+            #(#docs)*
+            fn #fn_ident(
+                &mut self,
+                #(#arg_ident: #arg_ty),*
+            ) -> ::core::pin::Pin<::drone_core::_rt::alloc::boxed::Box<
+                dyn ::core::future::Future<
+                    Output = ::core::result::Result<#output, Self::Error>,
+                > + ::core::marker::Send + '_,
+            >>;
+        });
+    }
+    if cmds.is_empty() {
+        cmd_res_fields.push(quote!(_empty: ()));
+    }
+    if reqs.is_empty() {
+        req_res_fields.push(quote!(_empty: ()));
+    }
+
+    Ok(quote! {
+        #[doc = concat!("Commands of [`", stringify!(#ident), "`].")]
+        #vis enum #cmd_ident {
+            #(#cmd_variants,)*
+        }
+
+        #[doc = concat!("Command results of [`", stringify!(#ident), "`].")]
+        #[allow(missing_docs)]
+        #vis union #cmd_res_ident {
+            #(#cmd_res_fields,)*
+        }
+
+        #[doc = concat!("Requests of [`", stringify!(#ident), "`].")]
+        #vis enum #req_ident {
+            #(#req_variants,)*
+        }
+
+        #[doc = concat!("Request results of [`", stringify!(#ident), "`].")]
+        #[allow(missing_docs)]
+        #vis union #req_res_ident {
+            #(#req_res_fields,)*
+        }
+
+        #(#attrs)*
+        impl #trait_path for #ident {
+            type Cmd = #cmd_ident;
+            type CmdRes = #cmd_res_ident;
+            type Req = #req_ident;
+            type ReqRes = #req_res_ident;
+
+            #(#items)*
+
+            #[allow(unused_variables)]
+            fn run_cmd(cmd: #cmd_ident, context: Self::Context) -> #cmd_res_ident {
+                match cmd {
+                    #(#cmd_arms,)*
+                }
+            }
+        }
+
+        impl #ident {
+            #(#cmd_fns)*
+        }
+
+        #[doc = concat!("Typed commands of [`", stringify!(#ident), "`] sessions.")]
+        #vis trait #sess_ident: ::drone_core::proc_loop::Sess<ProcLoop = #ident> {
+            #(#sess_decls)*
+        }
+
+        impl<T: ::drone_core::proc_loop::Sess<ProcLoop = #ident>> #sess_ident for T {
+            #(#sess_fns)*
+        }
+
+        #[doc = concat!("Typed requests of [`", stringify!(#ident), "`] commands.")]
+        #vis trait #context_ident: ::drone_core::proc_loop::Context<#req_ident, #req_res_ident> {
+            #(#context_decls)*
+        }
+
+        impl<T: ::drone_core::proc_loop::Context<#req_ident, #req_res_ident>> #context_ident for T {
+            #(#context_fns)*
+        }
+
+        #[doc = concat!("Handler of [`", stringify!(#ident), "`] requests.")]
+        #vis trait #handler_ident: ::core::marker::Send {
+            /// Request error type.
+            type Error: ::core::marker::Send;
+
+            #(#handler_decls)*
+        }
+
+        impl #req_ident {
+            /// Returns a future that will return a result for this request from
+            /// `handler`.
+            #[allow(unused_variables)]
+            #vis fn run<T: #handler_ident>(
+                self,
+                handler: &mut T,
+            ) -> ::core::pin::Pin<::drone_core::_rt::alloc::boxed::Box<
+                dyn ::core::future::Future<
+                    Output = ::core::result::Result<#req_res_ident, T::Error>,
+                > + ::core::marker::Send + '_,
+            >> {
+                match self {
+                    #(#req_arms,)*
+                }
+            }
+        }
+    })
+}
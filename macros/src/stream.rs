@@ -1,16 +1,20 @@
 use drone_config::{Layout, LAYOUT_CONFIG};
-use drone_macros_core::parse_error;
+use drone_macros_core::{parse_error, parse_ident};
 use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{format_ident, quote};
 use syn::parse::{Parse, ParseStream, Result};
-use syn::{parse_macro_input, Attribute, Ident, LitBool, LitStr, Token, Visibility};
+use syn::{
+    parenthesized, parse_macro_input, Attribute, Ident, LitBool, LitInt, LitStr, Token,
+    Visibility,
+};
 
 struct Input {
     layout: Ident,
     metadata: Metadata,
     instance: Instance,
     global: bool,
+    transport: Transport,
 }
 
 struct Metadata {
@@ -25,12 +29,18 @@ struct Instance {
     ident: Ident,
 }
 
+enum Transport {
+    Drone,
+    Rtt { up: LitInt, down: LitInt },
+}
+
 impl Parse for Input {
     fn parse(input: ParseStream<'_>) -> Result<Self> {
         let mut layout = None;
         let mut metadata = None;
         let mut instance = None;
         let mut global = None;
+        let mut transport = None;
         while !input.is_empty() {
             let attrs = input.call(Attribute::parse_outer)?;
             let ident = input.parse::<Ident>()?;
@@ -59,6 +69,12 @@ impl Parse for Input {
                 } else {
                     return Err(input.error("multiple `global` specifications"));
                 }
+            } else if attrs.is_empty() && ident == "transport" {
+                if transport.is_none() {
+                    transport = Some(input.parse()?);
+                } else {
+                    return Err(input.error("multiple `transport` specifications"));
+                }
             } else {
                 return Err(input.error(format!("unknown key: `{ident}`")));
             }
@@ -71,10 +87,37 @@ impl Parse for Input {
             metadata: metadata.ok_or_else(|| input.error("missing `metadata` specification"))?,
             instance: instance.ok_or_else(|| input.error("missing `instance` specification"))?,
             global: global.unwrap_or(false),
+            transport: transport.unwrap_or(Transport::Drone),
         })
     }
 }
 
+impl Parse for Transport {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        if ident == "drone" {
+            Ok(Self::Drone)
+        } else if ident == "rtt" {
+            let content;
+            parenthesized!(content in input);
+            parse_ident!(content, "up");
+            content.parse::<Token![:]>()?;
+            let up = content.parse::<LitInt>()?;
+            content.parse::<Token![,]>()?;
+            parse_ident!(content, "down");
+            content.parse::<Token![:]>()?;
+            let down = content.parse::<LitInt>()?;
+            content.parse::<Option<Token![,]>>()?;
+            if up.base10_parse::<u8>()? == 0 {
+                return Err(syn::Error::new(up.span(), "at least one up channel is required"));
+            }
+            Ok(Self::Rtt { up, down })
+        } else {
+            Err(syn::Error::new(ident.span(), "unknown transport"))
+        }
+    }
+}
+
 impl Metadata {
     fn parse(input: ParseStream<'_>, attrs: Vec<Attribute>) -> Result<Self> {
         let vis = input.parse()?;
@@ -93,7 +136,8 @@ impl Instance {
 
 #[allow(clippy::too_many_lines)]
 pub fn proc_macro(input: TokenStream) -> TokenStream {
-    let Input { layout: stream_layout, metadata, instance, global } = parse_macro_input!(input);
+    let Input { layout: stream_layout, metadata, instance, global, transport } =
+        parse_macro_input!(input);
     let Metadata { attrs: metadata_attrs, vis: metadata_vis, ident: metadata_ident } = &metadata;
     let Instance { attrs: instance_attrs, vis: instance_vis, ident: instance_ident } = &instance;
     let layout = match Layout::read_from_cargo() {
@@ -115,14 +159,17 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
     let init_ident =
         if init_primary { format_ident!("init_primary") } else { format_ident!("init") };
     let section = LitStr::new(&format!(".stream_{stream_layout}_rt"), Span::call_site());
-    let global = global.then(|| def_global(&instance));
+    let (fields, zeroed, init) = match &transport {
+        Transport::Drone => def_drone(instance_ident, buffer_size, init_primary),
+        Transport::Rtt { up, down } => def_rtt(instance_ident, buffer_size, init_primary, up, down),
+    };
+    let global = global.then(|| def_global(&instance, &transport));
 
     quote! {
         #(#metadata_attrs)*
         #[repr(C)]
         #metadata_vis struct #metadata_ident {
-            /// Drone Stream runtime structure.
-            pub runtime: ::drone_core::_rt::drone_stream::Runtime,
+            #fields
         }
 
         #(#instance_attrs)*
@@ -135,7 +182,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
             #[must_use]
             #[inline]
             pub const fn zeroed() -> Self {
-                Self { runtime: ::drone_core::_rt::drone_stream::Runtime::zeroed() }
+                #zeroed
             }
 
             /// Initializes this Drone Stream runtime.
@@ -146,11 +193,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
             #[inline]
             pub unsafe fn #init_ident() {
                 unsafe {
-                    ::drone_core::stream::init(
-                        ::core::ptr::addr_of_mut!((*#instance_ident.get()).runtime),
-                        #buffer_size,
-                        #init_primary,
-                    );
+                    #init
                 }
             }
         }
@@ -160,12 +203,109 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
     .into()
 }
 
-fn def_global(instance: &Instance) -> TokenStream2 {
+fn def_drone(
+    instance_ident: &Ident,
+    buffer_size: u32,
+    init_primary: bool,
+) -> (TokenStream2, TokenStream2, TokenStream2) {
+    let fields = quote! {
+        /// Drone Stream runtime structure.
+        pub runtime: ::drone_core::_rt::drone_stream::Runtime,
+    };
+    let zeroed = quote! {
+        Self { runtime: ::drone_core::_rt::drone_stream::Runtime::zeroed() }
+    };
+    let init = quote! {
+        ::drone_core::stream::init(
+            ::core::ptr::addr_of_mut!((*#instance_ident.get()).runtime),
+            #buffer_size,
+            #init_primary,
+        );
+    };
+    (fields, zeroed, init)
+}
+
+fn def_rtt(
+    instance_ident: &Ident,
+    buffer_size: u32,
+    init_primary: bool,
+    up: &LitInt,
+    down: &LitInt,
+) -> (TokenStream2, TokenStream2, TokenStream2) {
+    let control_block = quote!(::drone_core::stream::rtt::ControlBlock<#up, #down>);
+    let down_size = quote!(::drone_core::stream::rtt::DOWN_BUFFER_SIZE);
+    let up_size = quote! {
+        (#buffer_size as usize
+            - ::core::mem::size_of::<#control_block>()
+            - #down * #down_size)
+            / #up
+    };
+    let fields = quote! {
+        /// RTT control block.
+        pub control_block: #control_block,
+        /// RTT up buffers, one per stream number.
+        pub up_buffers: [[u8; #up_size]; #up],
+        /// RTT down buffers.
+        pub down_buffers: [[u8; #down_size]; #down],
+    };
+    let zeroed = quote! {
+        Self {
+            control_block: <#control_block>::zeroed(),
+            up_buffers: [[0; #up_size]; #up],
+            down_buffers: [[0; #down_size]; #down],
+        }
+    };
+    let init = quote! {
+        ::drone_core::stream::init_rtt(#init_primary);
+        let this = #instance_ident.get();
+        <#control_block>::init(
+            ::core::ptr::addr_of_mut!((*this).control_block),
+            ::core::ptr::addr_of_mut!((*this).up_buffers).cast::<u8>(),
+            (#up_size) as u32,
+            ::core::ptr::addr_of_mut!((*this).down_buffers).cast::<u8>(),
+            #down_size as u32,
+        );
+    };
+    (fields, zeroed, init)
+}
+
+fn def_global(instance: &Instance, transport: &Transport) -> TokenStream2 {
     let Instance { ident: instance_ident, .. } = instance;
+    let (runtime, rtt) = match transport {
+        Transport::Drone => {
+            let check = quote! {
+                const _: () = ::core::assert!(
+                    !::drone_core::stream::rtt::GLOBAL,
+                    "the global stream must use the RTT transport with the `rtt` feature",
+                );
+            };
+            (quote!(::core::ptr::addr_of_mut!((*#instance_ident.get()).runtime)), check)
+        }
+        Transport::Rtt { .. } => {
+            let rtt = quote! {
+                const _: () = ::core::assert!(
+                    ::drone_core::stream::rtt::GLOBAL,
+                    "the RTT transport of the global stream requires the `rtt` feature",
+                );
+
+                #[no_mangle]
+                extern "C" fn drone_stream_rtt() -> *mut ::drone_core::stream::rtt::Header {
+                    unsafe {
+                        ::drone_core::stream::rtt::ControlBlock::header(::core::ptr::addr_of_mut!(
+                            (*#instance_ident.get()).control_block
+                        ))
+                    }
+                }
+            };
+            (quote!(::core::ptr::null_mut()), rtt)
+        }
+    };
     quote! {
         #[no_mangle]
         extern "C" fn drone_stream_runtime() -> *mut ::drone_core::_rt::drone_stream::Runtime {
-            unsafe { ::core::ptr::addr_of_mut!((*#instance_ident.get()).runtime) }
+            unsafe { #runtime }
         }
+
+        #rtt
     }
 }
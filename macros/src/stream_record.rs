@@ -0,0 +1,58 @@
+use drone_macros_core::parse_error;
+use if_chain::if_chain;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Meta, NestedMeta};
+
+pub fn proc_macro_derive(input: TokenStream) -> TokenStream {
+    let DeriveInput { attrs, ident, generics, data, .. } = parse_macro_input!(input);
+    let data = match data {
+        Data::Struct(data) => data,
+        Data::Enum(_) | Data::Union(_) => {
+            parse_error!("StreamRecord can be derived only from a struct");
+        }
+    };
+    if !generics.params.is_empty() {
+        parse_error!("StreamRecord can't be derived for a generic struct");
+    }
+    if !attrs.iter().any(is_repr_c) {
+        parse_error!("StreamRecord can be derived only from a `#[repr(C)]` struct");
+    }
+    let field_ty = data.fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+
+    quote! {
+        unsafe impl ::drone_core::stream::StreamRecord for #ident {}
+
+        const _: () = {
+            fn assert_stream_record<T: ::drone_core::stream::StreamRecord>() {}
+            #[allow(dead_code)]
+            fn assert_fields() {
+                #(assert_stream_record::<#field_ty>();)*
+            }
+            ::core::assert!(
+                ::core::mem::size_of::<#ident>()
+                    == 0 #(+ ::core::mem::size_of::<#field_ty>())*,
+                "StreamRecord struct must not contain padding",
+            );
+            ::core::assert!(
+                ::core::mem::size_of::<#ident>() <= 255,
+                "StreamRecord struct must not exceed 255 bytes",
+            );
+        };
+    }
+    .into()
+}
+
+fn is_repr_c(attr: &Attribute) -> bool {
+    if_chain! {
+        if attr.path.is_ident("repr");
+        if let Ok(Meta::List(list)) = attr.parse_meta();
+        then {
+            list.nested.iter().any(|nested| {
+                matches!(nested, NestedMeta::Meta(Meta::Path(path)) if path.is_ident("C"))
+            })
+        } else {
+            false
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use heck::ToUpperCamelCase;
+use if_chain::if_chain;
 use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{format_ident, quote};
 use syn::parse::{Parse, ParseStream, Result};
 use syn::{
-    braced, parse_macro_input, Attribute, Expr, ExprPath, Ident, LitInt, Token, Type, Visibility,
+    braced, parse_macro_input, Attribute, Expr, ExprPath, Ident, Lit, LitInt, LitStr, Meta, Token,
+    Type, Visibility,
 };
 
 struct Input {
@@ -222,6 +224,14 @@ fn def_thr(
             #thr_ident::new(#idx)
         });
     }
+    let names = threads
+        .iter()
+        .map(|Thread { ident, .. }| LitStr::new(&ident.to_string(), Span::call_site()))
+        .collect::<Vec<_>>();
+    let docs = threads
+        .iter()
+        .map(|Thread { attrs, .. }| LitStr::new(&collect_doc(attrs), Span::call_site()))
+        .collect::<Vec<_>>();
     let mut thr_tokens = Vec::new();
     let mut thr_ctor_tokens = Vec::new();
     for Field { attrs, vis, ident, ty, init } in thr_fields {
@@ -265,6 +275,18 @@ fn def_thr(
                 &CURRENT
             }
 
+            #[inline]
+            fn name(thr_idx: u16) -> &'static str {
+                const NAMES: [&str; #count as usize] = [#(#names),*];
+                NAMES[thr_idx as usize]
+            }
+
+            #[inline]
+            fn doc(thr_idx: u16) -> &'static str {
+                const DOCS: [&str; #count as usize] = [#(#docs),*];
+                DOCS[thr_idx as usize]
+            }
+
             #[inline]
             fn fib_chain(&self) -> &::drone_core::fib::Chain {
                 &self.fib_chain
@@ -280,6 +302,26 @@ fn def_thr(
     }
 }
 
+fn collect_doc(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter_map(|attr| {
+            if_chain! {
+                if attr.path.is_ident("doc");
+                if let Ok(Meta::NameValue(meta)) = attr.parse_meta();
+                if let Lit::Str(doc) = meta.lit;
+                then {
+                    let doc = doc.value();
+                    Some(doc.strip_prefix(' ').unwrap_or(&doc).to_owned())
+                } else {
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn def_local(local: &Local) -> TokenStream2 {
     let Local { vis: local_vis, attrs: local_attrs, ident: local_ident, fields: local_fields } =
         &local;
@@ -2,7 +2,9 @@ use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::parse::{Parse, ParseStream, Result};
-use syn::{braced, parse_macro_input, Attribute, ExprPath, Ident, Token, Visibility};
+use syn::{
+    braced, parse_macro_input, Attribute, ExprPath, Ident, LitBool, LitInt, Token, Visibility,
+};
 
 struct Input {
     thr: Thr,
@@ -11,6 +13,9 @@ struct Input {
     threads: Threads,
     resume: Option<ExprPath>,
     set_pending: Option<ExprPath>,
+    idle: Option<ExprPath>,
+    priority_levels: Option<LitInt>,
+    round_robin: Option<LitBool>,
 }
 
 struct Thr {
@@ -45,6 +50,9 @@ impl Parse for Input {
         let mut threads = None;
         let mut resume = None;
         let mut set_pending = None;
+        let mut idle = None;
+        let mut priority_levels = None;
+        let mut round_robin = None;
         while !input.is_empty() {
             let attrs = input.call(Attribute::parse_outer)?;
             let ident = input.parse::<Ident>()?;
@@ -85,6 +93,28 @@ impl Parse for Input {
                 } else {
                     return Err(input.error("multiple `set_pending` specifications"));
                 }
+            } else if attrs.is_empty() && ident == "idle" {
+                if idle.is_none() {
+                    idle = Some(input.parse()?);
+                } else {
+                    return Err(input.error("multiple `idle` specifications"));
+                }
+            } else if attrs.is_empty() && ident == "priority_levels" {
+                if priority_levels.is_none() {
+                    let levels = input.parse::<LitInt>()?;
+                    if levels.base10_parse::<u8>()? == 0 {
+                        return Err(input.error("`priority_levels` must be positive"));
+                    }
+                    priority_levels = Some(levels);
+                } else {
+                    return Err(input.error("multiple `priority_levels` specifications"));
+                }
+            } else if attrs.is_empty() && ident == "round_robin" {
+                if round_robin.is_none() {
+                    round_robin = Some(input.parse()?);
+                } else {
+                    return Err(input.error("multiple `round_robin` specifications"));
+                }
             } else {
                 return Err(input.error(format!("unknown key: `{ident}`")));
             }
@@ -99,6 +129,9 @@ impl Parse for Input {
             threads: threads.ok_or_else(|| input.error("missing `threads` specification"))?,
             resume,
             set_pending,
+            idle,
+            priority_levels,
+            round_robin,
         })
     }
 }
@@ -143,9 +176,25 @@ impl Parse for Threads {
 }
 
 pub fn proc_macro(input: TokenStream) -> TokenStream {
-    let Input { thr, local, index, threads, resume, set_pending } = parse_macro_input!(input);
+    let Input {
+        thr,
+        local,
+        index,
+        threads,
+        resume,
+        set_pending,
+        idle,
+        priority_levels,
+        round_robin,
+    } = parse_macro_input!(input);
     let def_pool = def_pool(&thr, &local, &index, &threads, resume.as_ref());
-    let def_soft = def_soft(&thr, set_pending.as_ref());
+    let def_soft = def_soft(
+        &thr,
+        set_pending.as_ref(),
+        idle.as_ref(),
+        priority_levels.as_ref(),
+        round_robin.as_ref(),
+    );
 
     quote! {
         #def_pool
@@ -194,8 +243,24 @@ fn def_pool(
     }
 }
 
-fn def_soft(thr: &Thr, set_pending: Option<&ExprPath>) -> TokenStream2 {
+fn def_soft(
+    thr: &Thr,
+    set_pending: Option<&ExprPath>,
+    idle: Option<&ExprPath>,
+    priority_levels: Option<&LitInt>,
+    round_robin: Option<&LitBool>,
+) -> TokenStream2 {
     let Thr { ident: thr_ident, .. } = thr;
+    let priority_levels = priority_levels.map(|priority_levels| {
+        quote! {
+            const PRIORITY_LEVELS: u8 = #priority_levels;
+        }
+    });
+    let round_robin = round_robin.map(|round_robin| {
+        quote! {
+            const ROUND_ROBIN: bool = #round_robin;
+        }
+    });
     let set_pending = set_pending.map(|set_pending| {
         quote! {
             #[inline]
@@ -204,9 +269,20 @@ fn def_soft(thr: &Thr, set_pending: Option<&ExprPath>) -> TokenStream2 {
             }
         }
     });
+    let idle = idle.map(|idle| {
+        quote! {
+            #[inline]
+            fn idle() {
+                #idle();
+            }
+        }
+    });
 
     quote! {
         unsafe impl ::drone_core::thr::SoftThread for #thr_ident {
+            #priority_levels
+            #round_robin
+
             #[inline]
             fn pending() -> *const ::drone_core::thr::PendingState {
                 #[allow(clippy::declare_interior_mutable_const)]
@@ -223,6 +299,7 @@ fn def_soft(thr: &Thr, set_pending: Option<&ExprPath>) -> TokenStream2 {
             }
 
             #set_pending
+            #idle
         }
     }
 }
@@ -0,0 +1,232 @@
+//! Persistent crash log.
+//!
+//! After a panic, the device is usually reset, and the panic message is lost
+//! unless a debug probe was attached. This module stores the panic message,
+//! location, and the current thread into the `.noinit` link section,
+//! which is not initialized at startup and survives a reset. The record is
+//! protected with a checksum, so garbage left in RAM after a power-up is not
+//! mistaken for a crash.
+//!
+//! # Examples
+//!
+//! Register the hook at startup, then check the log on the next boot. The
+//! current thread is looked up in the thread pools registered with
+//! [`panic::register_threads`]:
+//!
+//! ```no_run
+//! use drone_core::thr::Thread;
+//! use drone_core::{crash, panic, println};
+//!
+//! fn init<T: Thread>() {
+//!     if let Some(report) = crash::take() {
+//!         println!("rebooted after a crash: {report}");
+//!     }
+//!     panic::register_threads::<T>();
+//!     panic::set_hook(crash::hook);
+//! }
+//! ```
+//!
+//! [`panic::register_threads`]: crate::panic::register_threads
+
+use crate::panic;
+use core::cell::SyncUnsafeCell;
+use core::fmt::{self, Write};
+use core::mem::MaybeUninit;
+use core::panic::PanicInfo;
+use core::{ptr, str};
+
+/// Maximum length of the stored panic message in bytes.
+pub const MESSAGE_CAPACITY: usize = 128;
+
+/// Maximum length of the stored source file name in bytes.
+pub const FILE_CAPACITY: usize = 64;
+
+/// Maximum length of the stored thread name in bytes.
+pub const NAME_CAPACITY: usize = 32;
+
+const MAGIC: u32 = 0x4352_5348;
+
+#[link_section = ".noinit"]
+static CRASH_LOG: SyncUnsafeCell<MaybeUninit<CrashReport>> =
+    SyncUnsafeCell::new(MaybeUninit::uninit());
+
+/// A crash record.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct CrashReport {
+    magic: u32,
+    checksum: u32,
+    thread: u16,
+    name_len: u16,
+    file_len: u16,
+    message_len: u16,
+    line: u32,
+    column: u32,
+    name: [u8; NAME_CAPACITY],
+    file: [u8; FILE_CAPACITY],
+    message: [u8; MESSAGE_CAPACITY],
+}
+
+struct Buffer<'a> {
+    bytes: &'a mut [u8],
+    len: &'a mut u16,
+}
+
+/// Panic hook, which stores the crash record for the current thread, as
+/// returned by [`panic::current_thread`].
+///
+/// The hook returns, so the default panic behavior follows. See
+/// [`panic::set_hook`].
+pub fn hook(info: &PanicInfo<'_>) {
+    record(info, panic::current_thread());
+}
+
+/// Stores the crash record for the panic `info` in the thread `thread`, given
+/// as its index and name.
+///
+/// `thread` is `None` if the panic happened outside of a thread pool.
+pub fn record(info: &PanicInfo<'_>, thread: Option<(u16, &str)>) {
+    let mut report = CrashReport {
+        magic: MAGIC,
+        checksum: 0,
+        thread: 0,
+        name_len: 0,
+        file_len: 0,
+        message_len: 0,
+        line: 0,
+        column: 0,
+        name: [0; NAME_CAPACITY],
+        file: [0; FILE_CAPACITY],
+        message: [0; MESSAGE_CAPACITY],
+    };
+    if let Some((thr_idx, name)) = thread {
+        report.thread = thr_idx + 1;
+        let _ = Buffer { bytes: &mut report.name, len: &mut report.name_len }.write_str(name);
+    }
+    if let Some(location) = info.location() {
+        report.line = location.line();
+        report.column = location.column();
+        let _ = Buffer { bytes: &mut report.file, len: &mut report.file_len }
+            .write_str(location.file());
+    }
+    if let Some(message) = info.message() {
+        let _ =
+            Buffer { bytes: &mut report.message, len: &mut report.message_len }.write_fmt(*message);
+    }
+    report.checksum = report.compute_checksum();
+    unsafe { CRASH_LOG.get().cast::<CrashReport>().write_volatile(report) };
+}
+
+/// Returns the stored crash record, or `None` if there is no valid record.
+pub fn last() -> Option<CrashReport> {
+    let report = unsafe { CRASH_LOG.get().cast::<CrashReport>().read_volatile() };
+    (report.magic == MAGIC
+        && usize::from(report.name_len) <= NAME_CAPACITY
+        && usize::from(report.file_len) <= FILE_CAPACITY
+        && usize::from(report.message_len) <= MESSAGE_CAPACITY
+        && report.checksum == report.compute_checksum())
+    .then_some(report)
+}
+
+/// Invalidates the stored crash record.
+pub fn clear() {
+    unsafe { ptr::addr_of_mut!((*CRASH_LOG.get().cast::<CrashReport>()).magic).write_volatile(0) };
+}
+
+/// Returns the stored crash record and invalidates it.
+pub fn take() -> Option<CrashReport> {
+    let report = last();
+    clear();
+    report
+}
+
+impl CrashReport {
+    /// Returns the thread index, or `None` if the panic happened outside of a
+    /// thread pool.
+    pub fn thread(&self) -> Option<u16> {
+        self.thread.checked_sub(1)
+    }
+
+    /// Returns the thread name, possibly truncated to [`NAME_CAPACITY`], or
+    /// `None` if the panic happened outside of a thread pool.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread()
+            .map(|_| str::from_utf8(&self.name[..usize::from(self.name_len)]).unwrap_or_default())
+    }
+
+    /// Returns the source file name of the panic location, possibly truncated
+    /// to [`FILE_CAPACITY`].
+    pub fn file(&self) -> &str {
+        str::from_utf8(&self.file[..usize::from(self.file_len)]).unwrap_or_default()
+    }
+
+    /// Returns the line number of the panic location.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Returns the column number of the panic location.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// Returns the panic message, possibly truncated to [`MESSAGE_CAPACITY`].
+    pub fn message(&self) -> &str {
+        str::from_utf8(&self.message[..usize::from(self.message_len)]).unwrap_or_default()
+    }
+
+    /// FNV-1a hash of the record, excluding `magic` and `checksum` fields.
+    fn compute_checksum(&self) -> u32 {
+        let mut hash = 0x811C_9DC5_u32;
+        let mut update = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash = (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193);
+            }
+        };
+        update(&self.thread.to_le_bytes());
+        update(&self.name_len.to_le_bytes());
+        update(&self.file_len.to_le_bytes());
+        update(&self.message_len.to_le_bytes());
+        update(&self.line.to_le_bytes());
+        update(&self.column.to_le_bytes());
+        update(&self.name);
+        update(&self.file);
+        update(&self.message);
+        hash
+    }
+}
+
+impl fmt::Debug for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CrashReport")
+            .field("thread", &self.thread())
+            .field("thread_name", &self.thread_name())
+            .field("file", &self.file())
+            .field("line", &self.line)
+            .field("column", &self.column)
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+impl fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(thr_idx), Some(name)) = (self.thread(), self.thread_name()) {
+            write!(f, "thread '{name}' (#{thr_idx}) ")?;
+        }
+        write!(f, "panicked at '{}', {}:{}:{}", self.message(), self.file(), self.line, self.column)
+    }
+}
+
+impl Write for Buffer<'_> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        let len = usize::from(*self.len);
+        let mut end = string.len().min(self.bytes.len() - len);
+        while !string.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.bytes[len..len + end].copy_from_slice(&string.as_bytes()[..end]);
+        *self.len += end as u16;
+        Ok(())
+    }
+}
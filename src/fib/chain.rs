@@ -84,6 +84,20 @@ impl Chain {
         // run concurrently because of the safety invariant of this function.
         unsafe { Drain { inner: self.list.drain_filter_raw(Node::filter) } }
     }
+
+    /// Like [`Chain::drain`], but calls `f` after each fiber is advanced.
+    #[cfg(feature = "host")]
+    pub(crate) unsafe fn drain_inspect<G: FnMut()>(
+        &self,
+        mut f: G,
+    ) -> Drain<'_, impl FnMut(*const ListNode<Node<()>>) -> bool> {
+        let filter = move |node| {
+            let complete = Node::filter(node);
+            f();
+            complete
+        };
+        unsafe { Drain { inner: self.list.drain_filter_raw(filter) } }
+    }
 }
 
 impl Drop for Chain {
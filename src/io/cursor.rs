@@ -0,0 +1,204 @@
+use crate::io::{Read, Seek, SeekFrom, Write};
+use core::future::{ready, Future};
+use core::pin::Pin;
+
+/// A `Cursor` wraps an in-memory buffer and provides it with a [`Seek`]
+/// implementation.
+///
+/// `Cursor`s are used with in-memory buffers, anything implementing
+/// [`AsRef`]`<[u8]>`, to allow them to implement [`Read`] and/or [`Write`],
+/// allowing these buffers to be used anywhere you might use a reader or writer
+/// that does actual I/O. All futures returned by a `Cursor` resolve on the
+/// first poll.
+///
+/// # Examples
+///
+/// ```
+/// use drone_core::io::{Cursor, Read, Seek, SeekFrom};
+///
+/// # async fn example() {
+/// let mut cursor = Cursor::new([1_u8, 2, 3, 4, 5]);
+/// let mut buf = [0; 2];
+/// cursor.seek(SeekFrom::End(-3)).await.unwrap();
+/// assert_eq!(cursor.read(&mut buf).await, Ok(2));
+/// assert_eq!(buf, [3, 4]);
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Cursor<T> {
+    inner: T,
+    pos: u64,
+}
+
+/// The error type for [`Cursor`] operations.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CursorError {
+    /// An attempt to seek to a negative position.
+    NegativePosition,
+    /// The resulting position doesn't fit into the address space.
+    Overflow,
+}
+
+impl<T> Cursor<T> {
+    /// Creates a new cursor wrapping the provided underlying in-memory buffer.
+    ///
+    /// Cursor initial position is `0` even if underlying buffer (e.g.,
+    /// [`Vec`]) is not empty. So writing to cursor starts with overwriting
+    /// [`Vec`] content, not with appending to it.
+    #[inline]
+    pub const fn new(inner: T) -> Self {
+        Self { inner, pos: 0 }
+    }
+
+    /// Consumes this cursor, returning the underlying value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Gets a reference to the underlying value in this cursor.
+    #[inline]
+    pub const fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying value in this cursor.
+    ///
+    /// Care should be taken to avoid modifying the internal I/O state of the
+    /// underlying value as it may corrupt this cursor's position.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Returns the current position of this cursor.
+    #[inline]
+    pub const fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Sets the position of this cursor.
+    #[inline]
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+impl<T: AsRef<[u8]>> Cursor<T> {
+    /// Returns the remaining slice from the current position.
+    ///
+    /// The returned slice is empty if the position is at or beyond the end of
+    /// the underlying buffer.
+    #[inline]
+    pub fn remaining_slice(&self) -> &[u8] {
+        let inner = self.inner.as_ref();
+        let pos = usize::try_from(self.pos).map_or(inner.len(), |pos| pos.min(inner.len()));
+        &inner[pos..]
+    }
+
+    /// Returns `true` if the remaining slice is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.remaining_slice().is_empty()
+    }
+
+    fn read_sync(&mut self, buf: &mut [u8]) -> usize {
+        let remaining = self.remaining_slice();
+        let count = remaining.len().min(buf.len());
+        buf[..count].copy_from_slice(&remaining[..count]);
+        self.pos += count as u64;
+        count
+    }
+
+    fn seek_sync(&mut self, pos: SeekFrom) -> Result<u64, CursorError> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(pos) => {
+                self.pos = pos;
+                return Ok(pos);
+            }
+            SeekFrom::End(offset) => (self.inner.as_ref().len() as u64, offset),
+            SeekFrom::Current(offset) => (self.pos, offset),
+        };
+        match base.checked_add_signed(offset) {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None if offset < 0 => Err(CursorError::NegativePosition),
+            None => Err(CursorError::Overflow),
+        }
+    }
+}
+
+impl<'sess, T: AsRef<[u8]>> Read<'sess> for Cursor<T> {
+    type Error = !;
+
+    fn read(
+        &'sess mut self,
+        buf: &'sess mut [u8],
+    ) -> Pin<Box<dyn Future<Output = Result<usize, Self::Error>> + Send + 'sess>> {
+        Box::pin(ready(Ok(self.read_sync(buf))))
+    }
+}
+
+impl<'sess, T: AsRef<[u8]>> Seek<'sess> for Cursor<T> {
+    type Error = CursorError;
+
+    fn seek(
+        &'sess mut self,
+        pos: SeekFrom,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, Self::Error>> + Send + 'sess>> {
+        Box::pin(ready(self.seek_sync(pos)))
+    }
+}
+
+impl Cursor<&mut [u8]> {
+    fn write_slice(&mut self, buf: &[u8]) -> usize {
+        let pos =
+            usize::try_from(self.pos).map_or(self.inner.len(), |pos| pos.min(self.inner.len()));
+        let count = (self.inner.len() - pos).min(buf.len());
+        self.inner[pos..pos + count].copy_from_slice(&buf[..count]);
+        self.pos += count as u64;
+        count
+    }
+}
+
+impl Cursor<Vec<u8>> {
+    fn write_vec(&mut self, buf: &[u8]) -> Result<usize, CursorError> {
+        let pos = usize::try_from(self.pos).map_err(|_| CursorError::Overflow)?;
+        let end = pos.checked_add(buf.len()).ok_or(CursorError::Overflow)?;
+        if self.inner.len() < pos {
+            self.inner.resize(pos, 0);
+        }
+        let overlap = (self.inner.len() - pos).min(buf.len());
+        self.inner[pos..pos + overlap].copy_from_slice(&buf[..overlap]);
+        self.inner.extend_from_slice(&buf[overlap..]);
+        self.pos = end as u64;
+        Ok(buf.len())
+    }
+}
+
+impl<'sess> Write<'sess> for Cursor<&mut [u8]> {
+    type Error = !;
+
+    /// Writes as many bytes as fit into the remaining part of the slice.
+    fn write(
+        &'sess mut self,
+        buf: &'sess [u8],
+    ) -> Pin<Box<dyn Future<Output = Result<usize, Self::Error>> + Send + 'sess>> {
+        Box::pin(ready(Ok(self.write_slice(buf))))
+    }
+}
+
+impl<'sess> Write<'sess> for Cursor<Vec<u8>> {
+    type Error = CursorError;
+
+    /// Writes the whole buffer, growing the vector if necessary. If the
+    /// position is beyond the end of the vector, the gap is filled with zeros.
+    fn write(
+        &'sess mut self,
+        buf: &'sess [u8],
+    ) -> Pin<Box<dyn Future<Output = Result<usize, Self::Error>> + Send + 'sess>> {
+        Box::pin(ready(self.write_vec(buf)))
+    }
+}
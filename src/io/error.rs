@@ -0,0 +1,155 @@
+use crate::io::CursorError;
+use core::fmt;
+
+/// A list specifying general categories of I/O error.
+///
+/// This list is intended to grow over time and it is not recommended to
+/// exhaustively match against it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// An entity was not found.
+    NotFound,
+    /// The operation lacked the necessary privileges to complete.
+    PermissionDenied,
+    /// The operation needs to block to complete, but the blocking operation
+    /// was requested to not occur.
+    WouldBlock,
+    /// A parameter was incorrect.
+    InvalidInput,
+    /// Data not valid for the operation were encountered.
+    InvalidData,
+    /// The I/O operation's timeout expired, causing it to be canceled.
+    TimedOut,
+    /// An error returned when an operation could not be completed because a
+    /// call to [`Write::write`](super::Write::write) returned `Ok(0)`.
+    WriteZero,
+    /// The operation was interrupted and can typically be retried.
+    Interrupted,
+    /// The operation could not be completed, because the underlying bus or
+    /// peripheral reported a fault.
+    BusFault,
+    /// This operation is unsupported by the I/O object.
+    Unsupported,
+    /// An error returned when an operation could not be completed because an
+    /// "end of file" was reached prematurely.
+    UnexpectedEof,
+    /// An operation could not be completed, because it failed to allocate
+    /// enough memory.
+    OutOfMemory,
+    /// A custom error that does not fall under any other I/O error kind.
+    Other,
+}
+
+/// I/O error.
+///
+/// Implement this trait for the associated error types of [`Read`],
+/// [`Write`], and [`Seek`] to allow generic code to handle errors of different
+/// I/O objects uniformly.
+///
+/// [`Read`]: super::Read
+/// [`Write`]: super::Write
+/// [`Seek`]: super::Seek
+pub trait Error: fmt::Debug {
+    /// Returns the general category of this error.
+    fn kind(&self) -> ErrorKind;
+}
+
+/// Error returned by [`read_exact`](super::read_exact).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReadExactError<E> {
+    /// An EOF was reached before the buffer was filled.
+    UnexpectedEof,
+    /// Error returned by the inner reader.
+    Other(E),
+}
+
+/// Error returned by [`write_all`](super::write_all).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WriteAllError<E> {
+    /// The writer returned `Ok(0)` before the whole buffer was written.
+    WriteZero,
+    /// Error returned by the inner writer.
+    Other(E),
+}
+
+impl ErrorKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::NotFound => "entity not found",
+            Self::PermissionDenied => "permission denied",
+            Self::WouldBlock => "operation would block",
+            Self::InvalidInput => "invalid input parameter",
+            Self::InvalidData => "invalid data",
+            Self::TimedOut => "timed out",
+            Self::WriteZero => "write zero",
+            Self::Interrupted => "operation interrupted",
+            Self::BusFault => "bus fault",
+            Self::Unsupported => "unsupported",
+            Self::UnexpectedEof => "unexpected end of file",
+            Self::OutOfMemory => "out of memory",
+            Self::Other => "other error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Error for ErrorKind {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        *self
+    }
+}
+
+impl Error for ! {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        match *self {}
+    }
+}
+
+impl Error for CursorError {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::InvalidInput
+    }
+}
+
+impl<E: Error> Error for ReadExactError<E> {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::UnexpectedEof => ErrorKind::UnexpectedEof,
+            Self::Other(err) => err.kind(),
+        }
+    }
+}
+
+impl<E: Error> Error for WriteAllError<E> {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::WriteZero => ErrorKind::WriteZero,
+            Self::Other(err) => err.kind(),
+        }
+    }
+}
+
+impl<E> From<E> for ReadExactError<E> {
+    #[inline]
+    fn from(err: E) -> Self {
+        Self::Other(err)
+    }
+}
+
+impl<E> From<E> for WriteAllError<E> {
+    #[inline]
+    fn from(err: E) -> Self {
+        Self::Other(err)
+    }
+}
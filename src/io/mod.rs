@@ -4,11 +4,22 @@
 //! and output. The most core part of this module is the [`Read`] and [`Write`]
 //! traits, which provide the most general interface for reading and writing
 //! input and output.
+//!
+//! [`Cursor`] provides an in-memory implementation of the I/O traits, which is
+//! useful for testing code written against them.
+//!
+//! The associated error types of the I/O traits are unconstrained. An
+//! implementation can opt into the [`Error`] trait to let generic code inspect
+//! the [`ErrorKind`] of a failure.
 
+mod cursor;
+mod error;
 mod read;
 mod seek;
 mod write;
 
-pub use self::read::Read;
+pub use self::cursor::{Cursor, CursorError};
+pub use self::error::{Error, ErrorKind, ReadExactError, WriteAllError};
+pub use self::read::{read_exact, Read};
 pub use self::seek::{Seek, SeekFrom};
-pub use self::write::Write;
+pub use self::write::{write_all, Write};
@@ -1,9 +1,14 @@
+use crate::io::ReadExactError;
 use core::future::Future;
+use core::mem;
 use core::pin::Pin;
 
 /// The `Read` trait allows for reading bytes from a source asynchronously.
 pub trait Read<'sess> {
     /// The error type returned by [`Read::read`].
+    ///
+    /// Implementing [`io::Error`](super::Error) for this type allows generic
+    /// code to distinguish different kinds of errors.
     type Error;
 
     /// Pull some bytes from this source into the specified buffer
@@ -13,3 +18,26 @@ pub trait Read<'sess> {
         buf: &'sess mut [u8],
     ) -> Pin<Box<dyn Future<Output = Result<usize, Self::Error>> + Send + 'sess>>;
 }
+
+/// Reads the exact number of bytes required to fill `buf` from `reader`.
+///
+/// # Errors
+///
+/// If the reader returns `Ok(0)` before the buffer is filled, this function
+/// returns [`ReadExactError::UnexpectedEof`]. The contents of `buf` are
+/// unspecified in this case.
+///
+/// Errors from the reader are returned as [`ReadExactError::Other`]
+/// immediately.
+pub async fn read_exact<R, E>(reader: &mut R, mut buf: &mut [u8]) -> Result<(), ReadExactError<E>>
+where
+    R: for<'sess> Read<'sess, Error = E>,
+{
+    while !buf.is_empty() {
+        match reader.read(&mut *buf).await? {
+            0 => return Err(ReadExactError::UnexpectedEof),
+            count => buf = &mut mem::take(&mut buf)[count..],
+        }
+    }
+    Ok(())
+}
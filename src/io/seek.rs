@@ -5,6 +5,9 @@ use core::pin::Pin;
 /// bytes asynchronously.
 pub trait Seek<'sess> {
     /// The error type returned by [`Seek::seek`].
+    ///
+    /// Implementing [`io::Error`](super::Error) for this type allows generic
+    /// code to distinguish different kinds of errors.
     type Error;
 
     /// Seek to an offset asynchronously, in bytes, in a stream.
@@ -28,6 +31,7 @@ pub trait Seek<'sess> {
 /// Enumeration of possible methods to seek within an I/O object.
 ///
 /// It is used by the [`Seek`] trait.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum SeekFrom {
     /// Sets the offset to the provided number of bytes.
     Start(u64),
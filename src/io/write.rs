@@ -1,9 +1,13 @@
+use crate::io::WriteAllError;
 use core::future::Future;
 use core::pin::Pin;
 
 /// The `Write` trait allows for writing bytes to a source asynchronously.
 pub trait Write<'sess> {
     /// The error type returned by [`Write::write`].
+    ///
+    /// Implementing [`io::Error`](super::Error) for this type allows generic
+    /// code to distinguish different kinds of errors.
     type Error;
 
     /// Write a buffer into this writer asynchronously, eventually returning how
@@ -13,3 +17,25 @@ pub trait Write<'sess> {
         buf: &'sess [u8],
     ) -> Pin<Box<dyn Future<Output = Result<usize, Self::Error>> + Send + 'sess>>;
 }
+
+/// Writes the entire `buf` into `writer`.
+///
+/// # Errors
+///
+/// If the writer returns `Ok(0)` before the whole buffer is written, this
+/// function returns [`WriteAllError::WriteZero`].
+///
+/// Errors from the writer are returned as [`WriteAllError::Other`]
+/// immediately.
+pub async fn write_all<W, E>(writer: &mut W, mut buf: &[u8]) -> Result<(), WriteAllError<E>>
+where
+    W: for<'sess> Write<'sess, Error = E>,
+{
+    while !buf.is_empty() {
+        match writer.write(buf).await? {
+            0 => return Err(WriteAllError::WriteZero),
+            count => buf = &buf[count..],
+        }
+    }
+    Ok(())
+}
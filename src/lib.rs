@@ -32,6 +32,7 @@
 #![feature(marker_trait_attr)]
 #![feature(never_type)]
 #![feature(nonnull_slice_from_raw_parts)]
+#![feature(panic_info_message)]
 #![feature(prelude_import)]
 #![feature(slice_ptr_get)]
 #![feature(sync_unsafe_cell)]
@@ -58,11 +59,13 @@ extern crate alloc;
 mod atomic_macros;
 
 pub mod bitfield;
+pub mod crash;
 pub mod fib;
 pub mod heap;
 pub mod inventory;
 pub mod io;
 pub mod mem;
+pub mod panic;
 pub mod periph;
 pub mod platform;
 pub mod prelude;
@@ -91,6 +94,11 @@ pub use drone_core_macros::override_layout;
 /// See [the module level documentation](mod@periph) for details.
 #[doc(inline)]
 pub use drone_core_macros::periph;
+/// Generates command and request types for a synchronous command loop.
+///
+/// See [the module level documentation](mod@proc_loop) for details.
+#[doc(inline)]
+pub use drone_core_macros::proc_loop;
 /// Defines a memory-mapped register.
 ///
 /// See [the module level documentation](mod@reg) for details.
@@ -105,5 +113,5 @@ pub use drone_core_macros::stream;
 /// Re-exports for use inside macros.
 #[doc(hidden)]
 pub mod _rt {
-    pub use ::{core, drone_stream};
+    pub use ::{alloc, core, drone_stream};
 }
@@ -0,0 +1,152 @@
+//! Panic and memory allocation error handling.
+//!
+//! By default, the panic handler prints the panic message to the standard
+//! error stream and resets the device with [`platform::reset`]. The memory
+//! allocation error handler does the same. Applications can register hooks,
+//! which run before the default behavior, e.g. to persist a crash record in
+//! no-init RAM or to blink an LED. If a hook doesn't return, e.g. it halts for
+//! a debugger, the default behavior is replaced entirely.
+//!
+//! A panic inside a hook skips the hooks and proceeds to the default behavior
+//! immediately.
+//!
+//! # Thread-aware reports
+//!
+//! Thread pools registered with [`register_threads`] are queried on panic, and
+//! the index and name of the currently executing thread are included in the
+//! report:
+//!
+//! ```text
+//! thread 'sys_tick' (#2) panicked at 'boom', src/main.rs:10:5
+//! ```
+//!
+//! Under the `host` feature, the panic and memory allocation error handlers
+//! are provided by the standard library, and the hooks are never called.
+//!
+//! # Examples
+//!
+//! ```
+//! use core::panic::PanicInfo;
+//! use drone_core::panic;
+//!
+//! fn halt(_info: &PanicInfo<'_>) {
+//!     loop {}
+//! }
+//!
+//! panic::set_hook(halt);
+//! ```
+//!
+//! [`platform::reset`]: crate::platform::reset
+
+#![cfg_attr(feature = "host", allow(dead_code))]
+
+use crate::sync::LinkedList;
+use crate::thr::Thread;
+use crate::{eprintln, platform};
+use core::alloc::Layout;
+use core::panic::PanicInfo;
+use core::{mem, ptr};
+
+#[cfg(any(feature = "atomics", loom))]
+type HookPtr = core::sync::atomic::AtomicPtr<()>;
+#[cfg(not(any(feature = "atomics", loom)))]
+type HookPtr = crate::sync::soft_atomic::Atomic<*mut ()>;
+
+#[cfg(any(feature = "atomics", loom))]
+type Panicking = core::sync::atomic::AtomicBool;
+#[cfg(not(any(feature = "atomics", loom)))]
+type Panicking = crate::sync::soft_atomic::Atomic<bool>;
+
+/// A panic hook.
+pub type PanicHook = fn(&PanicInfo<'_>);
+
+/// A memory allocation error hook.
+pub type AllocErrorHook = fn(Layout);
+
+static PANIC_HOOK: HookPtr = HookPtr::new(ptr::null_mut());
+static ALLOC_ERROR_HOOK: HookPtr = HookPtr::new(ptr::null_mut());
+static PANICKING: Panicking = Panicking::new(false);
+
+#[cfg(not(loom))]
+static THREAD_POOLS: LinkedList<fn() -> Option<(u16, &'static str)>> = LinkedList::new();
+#[cfg(loom)]
+loom::lazy_static! {
+    static ref THREAD_POOLS: LinkedList<fn() -> Option<(u16, &'static str)>> = LinkedList::new();
+}
+
+/// Registers a panic hook, replacing any previously registered hook.
+#[inline]
+pub fn set_hook(hook: PanicHook) {
+    store_atomic!(PANIC_HOOK, hook as *mut (), Release);
+}
+
+/// Unregisters the current panic hook and returns it.
+#[inline]
+pub fn take_hook() -> Option<PanicHook> {
+    let hook = swap_atomic!(PANIC_HOOK, ptr::null_mut(), AcqRel);
+    (!hook.is_null()).then(|| unsafe { mem::transmute::<*mut (), PanicHook>(hook) })
+}
+
+/// Registers a memory allocation error hook, replacing any previously
+/// registered hook.
+#[inline]
+pub fn set_alloc_error_hook(hook: AllocErrorHook) {
+    store_atomic!(ALLOC_ERROR_HOOK, hook as *mut (), Release);
+}
+
+/// Unregisters the current memory allocation error hook and returns it.
+#[inline]
+pub fn take_alloc_error_hook() -> Option<AllocErrorHook> {
+    let hook = swap_atomic!(ALLOC_ERROR_HOOK, ptr::null_mut(), AcqRel);
+    (!hook.is_null()).then(|| unsafe { mem::transmute::<*mut (), AllocErrorHook>(hook) })
+}
+
+/// Registers the thread pool `T` to be queried for the current thread on panic.
+///
+/// Registering the same pool twice has no effect other than a small memory
+/// overhead.
+pub fn register_threads<T: Thread>() {
+    THREAD_POOLS.push(|| T::current_index().map(|thr_idx| (thr_idx, T::name(thr_idx))));
+}
+
+/// Returns the index and the name of the currently executing thread of the
+/// first registered thread pool, which executes one.
+pub fn current_thread() -> Option<(u16, &'static str)> {
+    unsafe { THREAD_POOLS.iter_raw() }.find_map(|node| unsafe { (**node)() })
+}
+
+pub(crate) fn handle_panic(info: &PanicInfo<'_>) -> ! {
+    if enter() {
+        let hook = load_atomic!(PANIC_HOOK, Acquire);
+        if !hook.is_null() {
+            unsafe { mem::transmute::<*mut (), PanicHook>(hook)(info) };
+        }
+    }
+    match current_thread() {
+        Some((thr_idx, name)) => eprintln!("thread '{name}' (#{thr_idx}) {info}"),
+        None => eprintln!("{info}"),
+    }
+    platform::reset()
+}
+
+pub(crate) fn handle_alloc_error(layout: Layout) -> ! {
+    if enter() {
+        let hook = load_atomic!(ALLOC_ERROR_HOOK, Acquire);
+        if !hook.is_null() {
+            unsafe { mem::transmute::<*mut (), AllocErrorHook>(hook)(layout) };
+        }
+    }
+    let size = layout.size();
+    match current_thread() {
+        Some((thr_idx, name)) => {
+            eprintln!("thread '{name}' (#{thr_idx}) memory allocation of {size} bytes failed");
+        }
+        None => eprintln!("memory allocation of {size} bytes failed"),
+    }
+    platform::reset()
+}
+
+/// Returns `false` if called recursively from a hook.
+fn enter() -> bool {
+    !swap_atomic!(PANICKING, true, Relaxed)
+}
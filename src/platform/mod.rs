@@ -5,6 +5,7 @@
 mod interrputs;
 
 pub use self::interrputs::Interrupts;
+use crate::stream::rtt;
 use core::cell::UnsafeCell;
 use drone_stream::Runtime;
 
@@ -15,6 +16,8 @@ extern "C" {
     fn drone_data_mem_init(load: *const usize, base: *mut usize, end: *const usize);
     fn drone_zeroed_mem_init(base: *mut usize, end: *const usize);
     fn drone_stream_runtime() -> *mut Runtime;
+    #[cfg(feature = "rtt")]
+    fn drone_stream_rtt() -> *mut rtt::Header;
 }
 
 /// Runs a predicate in a tight loop. Stops when the predicate returns `false`.
@@ -157,12 +160,28 @@ pub unsafe fn data_mem_init(
 }
 
 /// Returns a mutable reference to the Drone Stream runtime.
+///
+/// Under the `host` feature, returns an in-process runtime of the current
+/// thread. See [`stream::host`](crate::stream::host) for details.
 #[inline]
 pub fn stream_rt() -> *mut Runtime {
     #[cfg(feature = "host")]
-    return unimplemented!();
+    return crate::stream::host::runtime();
     #[cfg(not(feature = "host"))]
     unsafe {
         drone_stream_runtime()
     }
 }
+
+/// Returns a pointer to the RTT control block header if the global stream
+/// uses the RTT transport, which is selected with the `rtt` feature.
+///
+/// Under the `host` feature, always returns `None`. See
+/// [`stream::rtt`](crate::stream::rtt) for details.
+#[inline]
+pub fn stream_rtt() -> Option<*mut rtt::Header> {
+    #[cfg(all(feature = "rtt", not(feature = "host")))]
+    return Some(unsafe { drone_stream_rtt() });
+    #[cfg(not(all(feature = "rtt", not(feature = "host"))))]
+    return None;
+}
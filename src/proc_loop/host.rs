@@ -0,0 +1,205 @@
+//! Host-side command loop sessions.
+//!
+//! Under the `host` feature, a command loop can be run without a platform
+//! crate. [`HostSess`] runs [`ProcLoop::run_cmd`] on a dedicated OS thread,
+//! which exchanges [`In`] and [`Out`] values with the session through
+//! rendezvous channels. The command loop must use [`HostContext`] as its
+//! [`ProcLoop::Context`], and requests are serviced by a handler closure
+//! passed to [`HostSess::new`].
+//!
+//! [`ProcLoop::on_create`] runs inside [`HostSess::new`],
+//! [`ProcLoop::on_enter`] runs on the command loop thread before the first
+//! command, and [`ProcLoop::on_drop`] runs when the session is dropped, after
+//! the command loop thread has exited.
+//!
+//! # Examples
+//!
+//! ```
+//! use drone_core::proc_loop::host::{HostContext, HostSess};
+//! use drone_core::proc_loop::{Context, ProcLoop, Sess};
+//! use futures::task::noop_waker_ref;
+//! use std::future::Future;
+//! use std::task::{Context as TaskContext, Poll};
+//!
+//! struct Adder;
+//!
+//! impl ProcLoop for Adder {
+//!     type Context = HostContext<u32, u32>;
+//!     type Cmd = u32;
+//!     type CmdRes = u32;
+//!     type Req = u32;
+//!     type ReqRes = u32;
+//!
+//!     const STACK_SIZE: usize = 0;
+//!
+//!     fn run_cmd(cmd: u32, context: Self::Context) -> u32 {
+//!         context.req(cmd) + 1
+//!     }
+//! }
+//!
+//! let mut sess = HostSess::<Adder, _>::new(|req| req * 2);
+//! let mut cx = TaskContext::from_waker(noop_waker_ref());
+//! assert!(matches!(sess.cmd(20).as_mut().poll(&mut cx), Poll::Ready(Ok(41))));
+//! ```
+
+use super::{Context, In, Out, ProcLoop, Sess, SessFuture};
+use crate::fib::{Fiber, FiberState};
+use core::any::Any;
+use core::cell::RefCell;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+std::thread_local! {
+    static REQ: RefCell<Option<Box<dyn Any>>> = RefCell::new(None);
+}
+
+/// A token that allows suspending a command loop run by [`HostSess`].
+pub struct HostContext<Req, ReqRes>(PhantomData<fn(Req) -> ReqRes>);
+
+/// A command loop session backed by an OS thread.
+pub struct HostSess<T: ProcLoop, F> {
+    fib: HostFiber<T>,
+    handler: F,
+}
+
+/// A fiber, which resumes a command loop running on an OS thread.
+pub struct HostFiber<T: ProcLoop> {
+    input: Option<SyncSender<In<T::Cmd, T::ReqRes>>>,
+    output: Receiver<Out<T::Req, T::CmdRes>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct Disconnected;
+
+impl<T, F> HostSess<T, F>
+where
+    T: ProcLoop<Context = HostContext<<T as ProcLoop>::Req, <T as ProcLoop>::ReqRes>>,
+    F: FnMut(T::Req) -> T::ReqRes + Send,
+{
+    /// Creates a new session and starts the command loop thread.
+    ///
+    /// Requests made by the command loop are passed to `handler`, and its
+    /// return values are passed back.
+    pub fn new(handler: F) -> Self {
+        T::on_create();
+        let (input_tx, input_rx) = sync_channel(0);
+        let (output_tx, output_rx) = sync_channel(0);
+        let thread = thread::spawn(move || run::<T>(input_rx, output_tx));
+        let fib = HostFiber { input: Some(input_tx), output: output_rx, thread: Some(thread) };
+        Self { fib, handler }
+    }
+}
+
+impl<T, F> Sess for HostSess<T, F>
+where
+    T: ProcLoop<Context = HostContext<<T as ProcLoop>::Req, <T as ProcLoop>::ReqRes>>,
+    F: FnMut(T::Req) -> T::ReqRes + Send,
+{
+    type Error = !;
+    type Fiber = HostFiber<T>;
+    type ProcLoop = T;
+
+    fn fib(&mut self) -> Pin<&mut Self::Fiber> {
+        Pin::new(&mut self.fib)
+    }
+
+    fn run_req(&mut self, req: T::Req) -> SessFuture<'_, Result<T::ReqRes, !>> {
+        Box::pin(async move { Ok((self.handler)(req)) })
+    }
+}
+
+impl<T: ProcLoop> Fiber for HostFiber<T> {
+    type Input = In<T::Cmd, T::ReqRes>;
+    type Return = !;
+    type Yield = Out<T::Req, T::CmdRes>;
+
+    fn resume(self: Pin<&mut Self>, input: Self::Input) -> FiberState<Self::Yield, !> {
+        let this = self.get_mut();
+        let sent = this.input.as_ref().map_or(false, |tx| tx.send(input).is_ok());
+        match this.output.recv() {
+            Ok(output) if sent => FiberState::Yielded(output),
+            _ => panic!("{} command loop panicked", core::any::type_name::<T>()),
+        }
+    }
+}
+
+impl<T: ProcLoop> Unpin for HostFiber<T> {}
+
+impl<T: ProcLoop> Drop for HostFiber<T> {
+    fn drop(&mut self) {
+        drop(self.input.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        T::on_drop();
+    }
+}
+
+impl<Req: 'static, ReqRes: 'static> Context<Req, ReqRes> for HostContext<Req, ReqRes> {
+    unsafe fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    fn req(self, req: Req) -> ReqRes {
+        let handler = REQ.with(|handler| {
+            handler
+                .borrow()
+                .as_ref()
+                .and_then(|handler| handler.downcast_ref::<Rc<dyn Fn(Req) -> ReqRes>>())
+                .cloned()
+        });
+        handler.expect("HostContext used outside of a host command loop")(req)
+    }
+}
+
+impl<Req, ReqRes> Clone for HostContext<Req, ReqRes> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Req, ReqRes> Copy for HostContext<Req, ReqRes> {}
+
+fn run<T>(input: Receiver<In<T::Cmd, T::ReqRes>>, output: SyncSender<Out<T::Req, T::CmdRes>>)
+where
+    T: ProcLoop<Context = HostContext<<T as ProcLoop>::Req, <T as ProcLoop>::ReqRes>>,
+{
+    let input = Rc::new(input);
+    let handler: Rc<dyn Fn(T::Req) -> T::ReqRes> = {
+        let input = Rc::clone(&input);
+        let output = output.clone();
+        Rc::new(move |req| {
+            if output.send(Out::Req(req)).is_err() {
+                disconnect();
+            }
+            match input.recv() {
+                Ok(req_res) => unsafe { req_res.into_req_res() },
+                Err(_) => disconnect(),
+            }
+        })
+    };
+    REQ.with(|cell| *cell.borrow_mut() = Some(Box::new(handler)));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        T::on_enter();
+        while let Ok(cmd) = input.recv() {
+            let res = T::run_cmd(unsafe { cmd.into_cmd() }, HostContext(PhantomData));
+            if output.send(Out::CmdRes(res)).is_err() {
+                break;
+            }
+        }
+    }));
+    REQ.with(|cell| cell.borrow_mut().take());
+    if let Err(payload) = result {
+        if !payload.is::<Disconnected>() {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+fn disconnect() -> ! {
+    panic::resume_unwind(Box::new(Disconnected))
+}
@@ -0,0 +1,346 @@
+//! This module provides interface to wrap a stackful synchronous code into an
+//! asynchronous command loop.
+//!
+//! **NOTE** A Drone platform crate may re-export this module with its own
+//! additions under the same name, in which case it should be used instead.
+//!
+//! # Typed commands
+//!
+//! Instead of writing [`ProcLoop::Cmd`], [`ProcLoop::CmdRes`],
+//! [`ProcLoop::Req`], and [`ProcLoop::ReqRes`] by hand, the
+//! [`proc_loop`](macro@crate::proc_loop) attribute macro generates them from
+//! method signatures. Methods marked with `#[cmd]` are commands, and take the
+//! context token as the first argument. Methods marked with `#[req]` are
+//! requests, and have no body. Other items are passed to the [`ProcLoop`]
+//! implementation as is. An optional visibility argument applies to the
+//! generated items.
+//!
+//! ```ignore
+//! use drone_core::proc_loop;
+//! use drone_core::proc_loop::ProcLoop;
+//!
+//! pub struct Fs;
+//!
+//! #[proc_loop(pub)]
+//! impl ProcLoop for Fs {
+//!     type Context = Context<FsReq, FsReqRes>;
+//!
+//!     const STACK_SIZE: usize = 0x800;
+//!
+//!     /// Returns the checksum of the block number `index`.
+//!     #[cmd]
+//!     fn checksum(context: Self::Context, index: u32) -> u32 {
+//!         context.read_block(index).iter().map(|&byte| u32::from(byte)).sum()
+//!     }
+//!
+//!     /// Reads the block number `index` from the storage.
+//!     #[req]
+//!     fn read_block(index: u32) -> [u8; 512];
+//! }
+//! ```
+//!
+//! For `Fs`, the macro generates:
+//!
+//! * `FsCmd`, `FsCmdRes`, `FsReq`, and `FsReqRes` types.
+//! * `FsSess` trait, which is implemented for every [`Sess`] of `Fs`, with a
+//!   typed method for each command, e.g. `sess.checksum(index).await`.
+//! * `FsContext` trait, which is implemented for every [`Context`] of `Fs`,
+//!   with a typed method for each request, e.g. `context.read_block(index)`.
+//! * `FsReqHandler` trait with a method for each request, and
+//!   `FsReq::run(handler)`, which can be returned from [`Sess::run_req`].
+//!
+//! # Stack usage
+//!
+//! [`ProcLoop::STACK_SIZE`] can be sized empirically. A platform crate paints
+//! the process stack with [`STACK_PAINT`] on creation using [`paint_stack`],
+//! and exposes the stack through [`Sess::stack`]. Then
+//! [`Sess::stack_high_water`] reports the maximum number of bytes ever used by
+//! the command loop, which can be asserted on in tests to detect near-overflows.
+//!
+//! # Stack overflow guard
+//!
+//! If [`ProcLoop::STACK_GUARD_SIZE`] is non-zero, the lowest bytes of the
+//! painted process stack form a guard region. [`Sess::cmd`] checks that the
+//! guard region is untouched every time the command loop suspends on
+//! [`Context::req`] or returns a command result, and reports an overflow with
+//! [`Sess::stack_overflow`] instead of letting the command loop silently
+//! corrupt adjacent memory.
+//!
+//! # Testing
+//!
+//! Under the `host` feature, the [`host`] module provides a [`Sess`]
+//! implementation backed by an OS thread, so that command loops can be tested
+//! without a platform crate.
+
+#![allow(clippy::wildcard_imports)]
+
+#[cfg(feature = "host")]
+pub mod host;
+
+use crate::fib;
+use crate::fib::Fiber;
+use core::future::Future;
+use core::mem;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+
+type SessFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The word, which an untouched process stack is filled with.
+pub const STACK_PAINT: u32 = 0xC5C5_C5C5;
+
+/// The trait for declaring a synchronous command loop.
+///
+/// This trait uses only associated items, thus it doesn't require the type to
+/// ever be instantiated.
+pub trait ProcLoop: Send + 'static {
+    /// Token type that allows suspending the task while waiting for a request
+    /// result.
+    type Context: Context<Self::Req, Self::ReqRes>;
+
+    /// `enum` of all possible commands.
+    type Cmd: Send + 'static;
+
+    /// `union` of all possible command results.
+    type CmdRes: Send + 'static;
+
+    /// `enum` of all possible requests.
+    type Req: Send + 'static;
+
+    /// `union` of all possible request results.
+    type ReqRes: Send + 'static;
+
+    /// Size of the process stack in bytes.
+    const STACK_SIZE: usize;
+
+    /// Size of the stack overflow guard region in bytes, which is a part of
+    /// [`ProcLoop::STACK_SIZE`]. The value of `0` disables the guard.
+    const STACK_GUARD_SIZE: usize = 0;
+
+    /// The commands runner.
+    ///
+    /// See [`ProcLoop`] for examples.
+    fn run_cmd(cmd: Self::Cmd, context: Self::Context) -> Self::CmdRes;
+
+    /// Runs on the process creation.
+    #[inline]
+    fn on_create() {}
+
+    /// Runs inside the synchronous context before the command loop.
+    #[inline]
+    fn on_enter() {}
+
+    /// Runs on the process destruction.
+    #[inline]
+    fn on_drop() {}
+}
+
+/// A session type for the synchronous command loop [`ProcLoop`].
+///
+/// A type that implements this trait should wrap the fiber for the command
+/// loop.
+pub trait Sess: Send {
+    /// The command loop interface.
+    type ProcLoop: ProcLoop;
+
+    /// Fiber that runs the command loop.
+    type Fiber: Fiber<
+            Input = In<<Self::ProcLoop as ProcLoop>::Cmd, <Self::ProcLoop as ProcLoop>::ReqRes>,
+            Yield = Out<<Self::ProcLoop as ProcLoop>::Req, <Self::ProcLoop as ProcLoop>::CmdRes>,
+            Return = !,
+        > + Send;
+
+    /// Request error type.
+    type Error: Send;
+
+    /// Returns a pinned mutable reference to the fiber.
+    fn fib(&mut self) -> Pin<&mut Self::Fiber>;
+
+    /// Returns the lowest address and the size in bytes of the process stack,
+    /// or `None` if the stack is not painted.
+    ///
+    /// The stack must be painted with [`paint_stack`] on creation.
+    #[inline]
+    fn stack(&self) -> Option<(*const u8, usize)> {
+        None
+    }
+
+    /// Returns the maximum number of bytes of the process stack used so far,
+    /// or `None` if the stack is not painted.
+    ///
+    /// The stack is assumed to grow downwards, and the value is computed by
+    /// scanning for the untouched region at the lowest addresses, so it has a
+    /// granularity of a word.
+    fn stack_high_water(&self) -> Option<usize> {
+        self.stack().map(|(bottom, size)| unsafe { stack_high_water(bottom, size) })
+    }
+
+    /// Returns `false` if the stack overflow guard region is corrupted.
+    ///
+    /// Always returns `true` if the stack is not painted or
+    /// [`ProcLoop::STACK_GUARD_SIZE`] is zero.
+    fn stack_guard_intact(&self) -> bool {
+        self.stack().map_or(true, |(bottom, size)| unsafe {
+            check_stack_guard(bottom, <Self::ProcLoop as ProcLoop>::STACK_GUARD_SIZE.min(size))
+        })
+    }
+
+    /// Returns an error for a detected stack overflow.
+    ///
+    /// The command loop can't be resumed after an overflow, and the session
+    /// should be dropped.
+    ///
+    /// # Panics
+    ///
+    /// The default implementation always panics.
+    fn stack_overflow(&mut self) -> Self::Error {
+        panic!("{} stack overflow", core::any::type_name::<Self::ProcLoop>());
+    }
+
+    /// Returns a future that will return a result for the request `req`.
+    fn run_req(
+        &mut self,
+        req: <Self::ProcLoop as ProcLoop>::Req,
+    ) -> SessFuture<'_, Result<<Self::ProcLoop as ProcLoop>::ReqRes, Self::Error>>;
+
+    /// Returns a future that will return a result for the command `cmd`.
+    fn cmd(
+        &mut self,
+        cmd: <Self::ProcLoop as ProcLoop>::Cmd,
+    ) -> SessFuture<'_, Result<<Self::ProcLoop as ProcLoop>::CmdRes, Self::Error>> {
+        let mut input = In::from_cmd(cmd);
+        Box::pin(async move {
+            loop {
+                let fib::Yielded(output) = self.fib().resume(input);
+                if !self.stack_guard_intact() {
+                    break Err(self.stack_overflow());
+                }
+                input = match output {
+                    Out::Req(req) => In::from_req_res(self.run_req(req).await?),
+                    Out::CmdRes(res) => break Ok(res),
+                }
+            }
+        })
+    }
+}
+
+/// Fills the stack of `size` bytes starting at `bottom` with [`STACK_PAINT`].
+///
+/// Unaligned bytes at the ends of the stack are left untouched.
+///
+/// # Safety
+///
+/// `bottom` must be valid for writes of `size` bytes, and the stack must not
+/// be in use.
+pub unsafe fn paint_stack(bottom: *mut u8, size: usize) {
+    unsafe {
+        let (words, count) = stack_words(bottom, size);
+        for i in 0..count {
+            (words as *mut u32).add(i).write_volatile(STACK_PAINT);
+        }
+    }
+}
+
+/// Returns the number of bytes of the stack of `size` bytes starting at
+/// `bottom`, which were used since it was painted with [`paint_stack`].
+///
+/// The stack is assumed to grow downwards.
+///
+/// # Safety
+///
+/// `bottom` must be valid for reads of `size` bytes.
+pub unsafe fn stack_high_water(bottom: *const u8, size: usize) -> usize {
+    unsafe {
+        let (words, count) = stack_words(bottom, size);
+        let untouched =
+            (0..count).take_while(|&i| words.add(i).read_volatile() == STACK_PAINT).count();
+        size - untouched * mem::size_of::<u32>()
+    }
+}
+
+/// Returns `true` if the stack guard region of `guard_size` bytes starting at
+/// `bottom` still contains only [`STACK_PAINT`].
+///
+/// A platform crate may also call this function from [`Context::req`], when
+/// it's executed on the process stack.
+///
+/// # Safety
+///
+/// `bottom` must be valid for reads of `guard_size` bytes.
+pub unsafe fn check_stack_guard(bottom: *const u8, guard_size: usize) -> bool {
+    unsafe {
+        let (words, count) = stack_words(bottom, guard_size);
+        (0..count).all(|i| words.add(i).read_volatile() == STACK_PAINT)
+    }
+}
+
+fn stack_words(bottom: *const u8, size: usize) -> (*const u32, usize) {
+    let offset = bottom.align_offset(mem::align_of::<u32>()).min(size);
+    (bottom.wrapping_add(offset).cast(), (size - offset) / mem::size_of::<u32>())
+}
+
+/// A token that allows suspending synchronous code.
+pub trait Context<Req, ReqRes>: Copy + 'static {
+    /// Creates a new token.
+    ///
+    /// # Safety
+    ///
+    /// It is unsafe to create a token inside an inappropriate context.
+    unsafe fn new() -> Self;
+
+    /// Makes a new request `req`.
+    ///
+    /// This method suspends execution of the current task allowing to escape
+    /// from synchronous code.
+    fn req(self, req: Req) -> ReqRes;
+}
+
+/// [`Sess::Fiber`] input.
+///
+/// See also [`Out`].
+pub union In<Cmd, ReqRes> {
+    /// Command to run by the command loop.
+    cmd: ManuallyDrop<Cmd>,
+    /// Result for the last request.
+    req_res: ManuallyDrop<ReqRes>,
+}
+
+/// [`Sess::Fiber`] output.
+///
+/// See also [`In`].
+pub enum Out<Req, CmdRes> {
+    /// Request that the command loop is waiting for.
+    Req(Req),
+    /// Result for the last command.
+    CmdRes(CmdRes),
+}
+
+impl<Cmd, ReqRes> In<Cmd, ReqRes> {
+    /// Creates a new command input.
+    pub fn from_cmd(cmd: Cmd) -> Self {
+        Self { cmd: ManuallyDrop::new(cmd) }
+    }
+
+    /// Creates a new request result input.
+    pub fn from_req_res(req_res: ReqRes) -> Self {
+        Self { req_res: ManuallyDrop::new(req_res) }
+    }
+
+    /// Interprets the input as a command.
+    ///
+    /// # Safety
+    ///
+    /// Whether the input is really a command object is unchecked.
+    pub unsafe fn into_cmd(self) -> Cmd {
+        ManuallyDrop::into_inner(unsafe { self.cmd })
+    }
+
+    /// Interprets the input as a request result.
+    ///
+    /// # Safety
+    ///
+    /// Whether the input is really a request result object is unchecked.
+    pub unsafe fn into_req_res(self) -> ReqRes {
+        ManuallyDrop::into_inner(unsafe { self.req_res })
+    }
+}
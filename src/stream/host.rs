@@ -0,0 +1,183 @@
+//! Host-side Drone Stream reader.
+//!
+//! Under the `host` feature, each OS thread gets its own in-process Drone
+//! Stream runtime, which is returned by [`platform::stream_rt`]. Writes go
+//! through the same framing code as on the target, and this module plays the
+//! role of a debug probe: it enables streams, decodes framed transactions per
+//! stream number, and sends bytes to [`stdin`](super::stdin).
+//!
+//! # Examples
+//!
+//! ```
+//! use drone_core::stream::host;
+//! use drone_core::{print, println};
+//!
+//! host::enable(0);
+//! print!("hello ");
+//! println!("world");
+//! assert_eq!(host::take_string(0), "hello world\n");
+//! ```
+//!
+//! [`platform::stream_rt`]: crate::platform::stream_rt
+
+use super::input::{InputRuntime, INPUT_BUFFER_SIZE};
+use super::GLOBAL_RT;
+use core::cell::UnsafeCell;
+use core::{ptr, slice};
+use drone_stream::{Runtime, HEADER_LENGTH};
+use std::sync::Mutex;
+
+/// Size of the ring buffer of the host-side runtime in bytes.
+pub const BUFFER_SIZE: u32 = 1024;
+
+const WRAP_MARKER: u8 = 0xFF;
+
+static ENABLE_LOCK: Mutex<()> = Mutex::new(());
+
+#[repr(C)]
+struct HostRuntime {
+    runtime: Runtime,
+    buffer: [u8; BUFFER_SIZE as usize],
+}
+
+struct Host {
+    runtime: HostRuntime,
+    input: InputRuntime,
+    transactions: Vec<(u8, Vec<u8>)>,
+}
+
+std::thread_local! {
+    static HOST: Box<UnsafeCell<Host>> = Box::new(UnsafeCell::new(Host {
+        runtime: HostRuntime {
+            runtime: Runtime { buffer_size: BUFFER_SIZE, read_cursor: 0, write_cursor: 0 },
+            buffer: [0; BUFFER_SIZE as usize],
+        },
+        input: InputRuntime::zeroed(),
+        transactions: Vec::new(),
+    }));
+}
+
+/// Enables the stream number `stream`, as a debug probe would do.
+///
+/// Streams are enabled globally for all threads.
+pub fn enable(stream: u8) {
+    modify_enable_mask(|mask| mask | 1 << stream);
+}
+
+/// Disables the stream number `stream`.
+pub fn disable(stream: u8) {
+    modify_enable_mask(|mask| mask & !(1 << stream));
+}
+
+/// Takes all transactions written to the stream number `stream` by the
+/// current thread so far.
+pub fn take_transactions(stream: u8) -> Vec<Vec<u8>> {
+    with_transactions(|transactions| {
+        let mut taken = Vec::new();
+        transactions.retain_mut(|(number, bytes)| {
+            if *number == stream {
+                taken.push(core::mem::take(bytes));
+                false
+            } else {
+                true
+            }
+        });
+        taken
+    })
+}
+
+/// Takes all bytes written to the stream number `stream` by the current
+/// thread so far.
+pub fn take_bytes(stream: u8) -> Vec<u8> {
+    take_transactions(stream).concat()
+}
+
+/// Takes all bytes written to the stream number `stream` by the current
+/// thread so far, and converts them to a string.
+///
+/// Invalid UTF-8 sequences are replaced with
+/// [`U+FFFD REPLACEMENT CHARACTER`](char::REPLACEMENT_CHARACTER).
+pub fn take_string(stream: u8) -> String {
+    String::from_utf8_lossy(&take_bytes(stream)).into_owned()
+}
+
+/// Discards all transactions written by the current thread so far.
+pub fn clear() {
+    with_transactions(Vec::clear);
+}
+
+/// Sends `bytes` to the standard input of the current thread, as a debug probe
+/// would do.
+///
+/// Returns the number of bytes sent, which is less than the length of `bytes`
+/// if the input ring buffer is full.
+pub fn send(bytes: &[u8]) -> usize {
+    HOST.with(|host| unsafe {
+        let input = ptr::addr_of_mut!((*host.get()).input);
+        let read_cursor = ptr::addr_of!((*input).read_cursor).read_volatile();
+        let mut write_cursor = ptr::addr_of!((*input).write_cursor).read_volatile();
+        let mut count = 0;
+        for &byte in bytes {
+            let next_write_cursor = (write_cursor + 1) % INPUT_BUFFER_SIZE;
+            if next_write_cursor == read_cursor {
+                break;
+            }
+            (*input).buffer[write_cursor as usize] = byte;
+            write_cursor = next_write_cursor;
+            count += 1;
+        }
+        ptr::addr_of_mut!((*input).write_cursor).write_volatile(write_cursor);
+        count
+    })
+}
+
+pub(crate) fn input_runtime() -> *mut InputRuntime {
+    HOST.with(|host| unsafe { ptr::addr_of_mut!((*host.get()).input) })
+}
+
+pub(crate) fn runtime() -> *mut Runtime {
+    HOST.with(|host| unsafe { ptr::addr_of_mut!((*host.get()).runtime.runtime) })
+}
+
+/// Reads all complete transactions from the ring buffer of the current thread,
+/// advancing the read cursor.
+pub(crate) fn drain() {
+    HOST.with(|host| unsafe {
+        let host = host.get();
+        let runtime = ptr::addr_of_mut!((*host).runtime.runtime);
+        let buffer = ptr::addr_of!((*host).runtime.buffer).cast::<u8>();
+        let transactions = &mut *ptr::addr_of_mut!((*host).transactions);
+        let buffer_size = ptr::addr_of!((*runtime).buffer_size).read_volatile();
+        let mut read_cursor = ptr::addr_of!((*runtime).read_cursor).read_volatile();
+        let write_cursor = ptr::addr_of!((*runtime).write_cursor).read_volatile();
+        while read_cursor != write_cursor {
+            let cursor = buffer.add(read_cursor as usize);
+            if *cursor == WRAP_MARKER {
+                read_cursor = 0;
+                continue;
+            }
+            let stream = *cursor;
+            let length = *cursor.add(1);
+            let source = slice::from_raw_parts(cursor.add(2), usize::from(length));
+            transactions.push((stream, source.to_vec()));
+            read_cursor += HEADER_LENGTH + u32::from(length);
+            if read_cursor == buffer_size {
+                read_cursor = 0;
+            }
+        }
+        ptr::addr_of_mut!((*runtime).read_cursor).write_volatile(read_cursor);
+    });
+}
+
+fn with_transactions<R>(f: impl FnOnce(&mut Vec<(u8, Vec<u8>)>) -> R) -> R {
+    drain();
+    HOST.with(|host| f(unsafe { &mut (*host.get()).transactions }))
+}
+
+fn modify_enable_mask(f: impl FnOnce(u32) -> u32) {
+    let _lock = ENABLE_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    unsafe {
+        let enable_mask = ptr::addr_of_mut!((*GLOBAL_RT.get()).enable_mask);
+        enable_mask.write_volatile(f(enable_mask.read_volatile()));
+    }
+}
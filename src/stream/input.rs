@@ -0,0 +1,216 @@
+use super::rtt;
+use crate::io::Read;
+use crate::platform::{stream_rtt, Interrupts};
+use crate::sync::LinkedList;
+use core::cell::SyncUnsafeCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::ptr;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::task::{Context, Poll, Waker};
+
+/// Size of the input ring buffer in bytes.
+pub const INPUT_BUFFER_SIZE: u32 = 256;
+
+#[link_section = ".stream_rt"]
+#[no_mangle]
+static INPUT_RT: SyncUnsafeCell<InputRuntime> = SyncUnsafeCell::new(InputRuntime::zeroed());
+
+#[cfg(not(loom))]
+static WAITERS: LinkedList<Waker> = LinkedList::new();
+#[cfg(loom)]
+loom::lazy_static! {
+    static ref WAITERS: LinkedList<Waker> = LinkedList::new();
+}
+
+/// Incremented each time [`WAITERS`] is emptied by [`poll_stdin`].
+static EPOCH: AtomicU32 = AtomicU32::new(0);
+
+/// Ring buffer for the host-to-target direction.
+///
+/// A debug probe writes bytes at `write_cursor` and advances it. The target
+/// reads bytes at `read_cursor` and advances it. The buffer is empty when the
+/// cursors are equal, and full when `write_cursor` is one byte behind
+/// `read_cursor`.
+#[repr(C)]
+pub(crate) struct InputRuntime {
+    pub(crate) read_cursor: u32,
+    pub(crate) write_cursor: u32,
+    pub(crate) buffer: [u8; INPUT_BUFFER_SIZE as usize],
+}
+
+/// A handle to the standard input stream.
+///
+/// Created by [`stdin`]. The bytes are sent by a debug probe through the input
+/// ring buffer, or through the down channel `0` with the RTT transport. Reading
+/// never returns `Ok(0)` for a non-empty buffer, it waits for the probe
+/// instead. The waiting readers are woken by [`poll_stdin`]. A pending read
+/// keeps at most one waker registered, and removes it when dropped. The bytes
+/// are consumed inside a critical section, so copies of the handle can read
+/// concurrently without losing or duplicating input.
+#[derive(Clone, Copy, Debug)]
+pub struct Stdin(());
+
+struct StdinRead<'a> {
+    buf: &'a mut [u8],
+    registered: Option<(Waker, u32)>,
+}
+
+/// Returns a handle to the standard input stream.
+///
+/// # Examples
+///
+/// ```no_run
+/// use drone_core::io::Read;
+/// use drone_core::stream;
+///
+/// async fn command() -> u8 {
+///     let mut stdin = stream::stdin();
+///     let mut buf = [0; 1];
+///     let _ = stdin.read(&mut buf).await;
+///     buf[0]
+/// }
+/// ```
+#[inline]
+pub fn stdin() -> Stdin {
+    Stdin(())
+}
+
+/// Wakes all [`Stdin`] readers waiting for input.
+///
+/// This is the polling hook for the standard input. It should be called
+/// periodically, e.g. from a timer interrupt handler, to check whether the
+/// debug probe has sent new bytes.
+#[inline(never)]
+#[export_name = "stream_poll_stdin"]
+pub fn poll_stdin() {
+    let waiters = LinkedList::new();
+    Interrupts::paused(|| unsafe {
+        while let Some(node) = WAITERS.pop_raw() {
+            waiters.push_raw(node);
+        }
+        EPOCH.store(EPOCH.load(Ordering::Relaxed).wrapping_add(1), Ordering::Relaxed);
+    });
+    for waker in waiters {
+        waker.wake();
+    }
+}
+
+impl InputRuntime {
+    pub(crate) const fn zeroed() -> Self {
+        Self { read_cursor: 0, write_cursor: 0, buffer: [0; INPUT_BUFFER_SIZE as usize] }
+    }
+}
+
+impl<'sess> Read<'sess> for Stdin {
+    type Error = !;
+
+    fn read(
+        &'sess mut self,
+        buf: &'sess mut [u8],
+    ) -> Pin<Box<dyn Future<Output = Result<usize, !>> + Send + 'sess>> {
+        Box::pin(StdinRead { buf, registered: None })
+    }
+}
+
+impl StdinRead<'_> {
+    fn register(&mut self, waker: &Waker) {
+        Interrupts::paused(|| {
+            let epoch = EPOCH.load(Ordering::Relaxed);
+            if let Some((registered, registered_epoch)) = &self.registered {
+                if *registered_epoch == epoch {
+                    if registered.will_wake(waker) {
+                        return;
+                    }
+                    unsafe { remove_waiter(registered) };
+                }
+            }
+            WAITERS.push(waker.clone());
+            self.registered = Some((waker.clone(), epoch));
+        });
+    }
+
+    fn deregister(&mut self) {
+        if let Some((registered, registered_epoch)) = self.registered.take() {
+            Interrupts::paused(|| {
+                // If the epoch has changed, the waker is already removed by
+                // `poll_stdin`.
+                if registered_epoch == EPOCH.load(Ordering::Relaxed) {
+                    unsafe { remove_waiter(&registered) };
+                }
+            });
+        }
+    }
+}
+
+impl Future for StdinRead<'_> {
+    type Output = Result<usize, !>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<usize, !>> {
+        if self.buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let mut count = unsafe { read(self.buf) };
+        if count == 0 {
+            self.register(cx.waker());
+            // The probe could have sent some bytes between the attempt and the
+            // registration.
+            count = unsafe { read(self.buf) };
+            if count == 0 {
+                return Poll::Pending;
+            }
+        }
+        self.deregister();
+        Poll::Ready(Ok(count))
+    }
+}
+
+impl Drop for StdinRead<'_> {
+    fn drop(&mut self) {
+        self.deregister();
+    }
+}
+
+pub(super) unsafe fn init() {
+    unsafe { input_rt().write_volatile(InputRuntime::zeroed()) };
+}
+
+/// Copies available bytes from the input ring buffer into `buf`, returning the
+/// number of bytes copied. The copy and the cursor update are done inside a
+/// critical section, so that concurrent readers don't consume the same bytes.
+unsafe fn read(buf: &mut [u8]) -> usize {
+    Interrupts::paused(|| unsafe {
+        if let Some(rtt) = stream_rtt() {
+            return rtt::read(rtt, 0, buf);
+        }
+        let rt = input_rt();
+        let buffer = ptr::addr_of!((*rt).buffer).cast::<u8>();
+        let mut read_cursor = ptr::addr_of!((*rt).read_cursor).read_volatile();
+        let write_cursor = ptr::addr_of!((*rt).write_cursor).read_volatile();
+        let mut count = 0;
+        while count < buf.len() && read_cursor != write_cursor {
+            buf[count] = buffer.add(read_cursor as usize).read_volatile();
+            read_cursor = (read_cursor + 1) % INPUT_BUFFER_SIZE;
+            count += 1;
+        }
+        ptr::addr_of_mut!((*rt).read_cursor).write_volatile(read_cursor);
+        count
+    })
+}
+
+/// Removes one waker, which wakes the same task as `waker`, from
+/// [`WAITERS`]. Must be called inside a critical section, so that it doesn't
+/// race with other removals.
+unsafe fn remove_waiter(waker: &Waker) {
+    let node = unsafe { WAITERS.drain_filter_raw(|node| (*node).will_wake(waker)).next() };
+    if let Some(node) = node {
+        drop(unsafe { Box::from_raw(node.cast_mut()) });
+    }
+}
+
+fn input_rt() -> *mut InputRuntime {
+    #[cfg(feature = "host")]
+    return super::host::input_runtime();
+    #[cfg(not(feature = "host"))]
+    return INPUT_RT.get();
+}
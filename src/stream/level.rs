@@ -0,0 +1,189 @@
+use super::{Stream, STREAM_COUNT};
+#[cfg(not(feature = "host"))]
+use crate::platform::Interrupts;
+use core::cell::SyncUnsafeCell;
+use core::fmt;
+
+#[link_section = ".stream_rt"]
+#[no_mangle]
+static LEVEL_RT: SyncUnsafeCell<[u8; STREAM_COUNT as usize]> =
+    SyncUnsafeCell::new([0; STREAM_COUNT as usize]);
+
+#[cfg(not(feature = "host"))]
+static MODULE_LEVELS: SyncUnsafeCell<&[(&str, Level)]> = SyncUnsafeCell::new(&[]);
+
+#[cfg(feature = "host")]
+static MODULE_LEVELS: std::sync::Mutex<&[(&str, Level)]> = std::sync::Mutex::new(&[]);
+
+/// Verbosity level of a record.
+///
+/// Levels are ordered from the least verbose [`Level::Error`] to the most
+/// verbose [`Level::Trace`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[repr(u8)]
+pub enum Level {
+    /// Designates very serious errors.
+    Error = 1,
+    /// Designates hazardous situations.
+    Warn = 2,
+    /// Designates useful information.
+    Info = 3,
+    /// Designates lower priority information.
+    Debug = 4,
+    /// Designates very low priority, often extremely verbose, information.
+    Trace = 5,
+}
+
+/// The most verbose level compiled into the firmware.
+///
+/// Records above this level are removed at compile-time. The value is
+/// controlled by `max-level-off`, `max-level-error`, `max-level-warn`,
+/// `max-level-info`, and `max-level-debug` cargo features of `drone-core`. The
+/// most restrictive enabled feature wins. If none is enabled, all levels are
+/// compiled in. The value of `0` means all leveled records are removed.
+pub const MAX_LEVEL: u8 = if cfg!(feature = "max-level-off") {
+    0
+} else if cfg!(feature = "max-level-error") {
+    Level::Error as u8
+} else if cfg!(feature = "max-level-warn") {
+    Level::Warn as u8
+} else if cfg!(feature = "max-level-info") {
+    Level::Info as u8
+} else if cfg!(feature = "max-level-debug") {
+    Level::Debug as u8
+} else {
+    Level::Trace as u8
+};
+
+/// The run-time level of a stream, for which a debug probe didn't set one.
+pub const DEFAULT_LEVEL: Level = Level::Info;
+
+impl Level {
+    /// Returns the level for the raw run-time `value`, or `None` if the value
+    /// doesn't correspond to any level.
+    #[inline]
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Error),
+            2 => Some(Self::Warn),
+            3 => Some(Self::Info),
+            4 => Some(Self::Debug),
+            5 => Some(Self::Trace),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this level is compiled in. See [`MAX_LEVEL`].
+    #[inline]
+    pub const fn is_compiled(self) -> bool {
+        self as u8 <= MAX_LEVEL
+    }
+
+    /// Returns `true` if a record of this level should be written to
+    /// `stream`.
+    ///
+    /// The level must be compiled in, the stream must be enabled by a debug
+    /// probe, and the level must not exceed the run-time level of the stream.
+    #[inline]
+    pub fn is_enabled(self, stream: Stream) -> bool {
+        self.is_compiled() && stream.is_enabled() && self <= stream.level()
+    }
+
+    /// Returns `true` if a record of this level from `module` should be
+    /// written to `stream`.
+    ///
+    /// Same as [`Level::is_enabled`], except that the level set for `module`
+    /// with [`set_module_levels`] takes precedence over the run-time level of
+    /// the stream.
+    #[inline]
+    pub fn is_enabled_in(self, stream: Stream, module: &str) -> bool {
+        self.is_compiled()
+            && stream.is_enabled()
+            && self <= module_level(module).unwrap_or_else(|| stream.level())
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+            Self::Trace => "TRACE",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl Stream {
+    /// Returns the run-time level of this stream.
+    ///
+    /// The levels are stored in the `LEVEL_RT` array next to the global Drone
+    /// Stream runtime, one byte per stream. A debug probe can write there to
+    /// change verbosity without reflashing. The value of `0` or an invalid
+    /// value means [`DEFAULT_LEVEL`].
+    #[inline]
+    pub fn level(self) -> Level {
+        let Self(stream) = self;
+        let value = unsafe { LEVEL_RT.get().cast::<u8>().add(usize::from(stream)).read_volatile() };
+        Level::from_u8(value).unwrap_or(DEFAULT_LEVEL)
+    }
+
+    /// Sets the run-time level of this stream.
+    #[inline]
+    pub fn set_level(self, level: Level) {
+        let Self(stream) = self;
+        unsafe {
+            LEVEL_RT.get().cast::<u8>().add(usize::from(stream)).write_volatile(level as u8);
+        }
+    }
+}
+
+/// Sets levels for modules, which take precedence over the run-time levels of
+/// streams.
+///
+/// Each entry of `levels` is a module path prefix and its level. An entry
+/// matches a module if the prefix is equal to the module path, or is followed
+/// by `::` in it. If several entries match, the longest prefix wins. Modules
+/// without a matching entry use the run-time level of the stream.
+///
+/// # Examples
+///
+/// ```
+/// use drone_core::stream;
+/// use drone_core::stream::Level;
+///
+/// stream::set_module_levels(&[("app", Level::Warn), ("app::radio", Level::Trace)]);
+/// ```
+pub fn set_module_levels(levels: &'static [(&'static str, Level)]) {
+    #[cfg(not(feature = "host"))]
+    Interrupts::paused(|| unsafe { *MODULE_LEVELS.get() = levels });
+    #[cfg(feature = "host")]
+    {
+        *MODULE_LEVELS.lock().unwrap() = levels;
+    }
+}
+
+fn module_level(module: &str) -> Option<Level> {
+    #[cfg(not(feature = "host"))]
+    let levels = Interrupts::paused(|| unsafe { *MODULE_LEVELS.get() });
+    #[cfg(feature = "host")]
+    let levels = *MODULE_LEVELS.lock().unwrap();
+    levels
+        .iter()
+        .filter(|&&(prefix, _)| {
+            module
+                .strip_prefix(prefix)
+                .map_or(false, |rest| rest.is_empty() || rest.starts_with("::"))
+        })
+        .max_by_key(|&&(prefix, _)| prefix.len())
+        .map(|&(_, level)| level)
+}
+
+pub(super) unsafe fn init() {
+    unsafe { LEVEL_RT.get().write_volatile([0; STREAM_COUNT as usize]) };
+}
@@ -0,0 +1,486 @@
+use super::Stream;
+#[cfg(feature = "host")]
+use core::fmt::Write;
+#[cfg(feature = "host")]
+use core::{fmt, str};
+
+/// Name of the link section, which holds interned format strings of
+/// [`log!`](crate::stream_log!).
+///
+/// The section is not supposed to be loaded into the target memory. The linker
+/// script should place it into a non-allocatable (`INFO`) output section, so
+/// that the address of each format string is just its offset within the
+/// section.
+pub const LOG_SECTION: &str = ".stream_log";
+
+const MAX_FRAME_LENGTH: usize = u8::MAX as usize;
+
+const TAG_U8: u8 = 0x00;
+const TAG_U16: u8 = 0x01;
+const TAG_U32: u8 = 0x02;
+const TAG_U64: u8 = 0x03;
+const TAG_I8: u8 = 0x04;
+const TAG_I16: u8 = 0x05;
+const TAG_I32: u8 = 0x06;
+const TAG_I64: u8 = 0x07;
+const TAG_F32: u8 = 0x08;
+const TAG_F64: u8 = 0x09;
+const TAG_BOOL: u8 = 0x0A;
+const TAG_CHAR: u8 = 0x0B;
+const TAG_STR: u8 = 0x0C;
+const TAG_BYTES: u8 = 0x0D;
+
+/// A value, which can be passed as an argument to
+/// [`log!`](crate::stream_log!).
+///
+/// The value is encoded as a type tag followed by its raw little-endian bytes.
+/// The formatting is deferred to the host-side [`LogDecoder`].
+pub trait LogArg {
+    /// Appends the encoded value to the `frame`.
+    fn encode(&self, frame: &mut LogFrame);
+}
+
+/// A single [`log!`](crate::stream_log!) record being encoded.
+///
+/// The frame consists of a 32-bit little-endian format string index followed
+/// by encoded arguments. The whole frame is written to the stream in one
+/// transaction. Arguments, which don't fit into the maximum transaction
+/// length, are truncated or dropped.
+pub struct LogFrame {
+    buffer: [u8; MAX_FRAME_LENGTH],
+    length: usize,
+}
+
+impl LogFrame {
+    /// Creates a new frame for the format string with the given `index`.
+    #[inline]
+    pub fn new(index: u32) -> Self {
+        let mut frame = Self { buffer: [0; MAX_FRAME_LENGTH], length: 0 };
+        frame.push(&index.to_le_bytes());
+        frame
+    }
+
+    /// Appends a type tag followed by `bytes` to the frame.
+    ///
+    /// If the whole value doesn't fit into the frame, the frame is left
+    /// unchanged.
+    #[inline]
+    pub fn push_tagged(&mut self, tag: u8, bytes: &[u8]) {
+        if self.length + 1 + bytes.len() <= MAX_FRAME_LENGTH {
+            self.push(&[tag]);
+            self.push(bytes);
+        }
+    }
+
+    /// Returns the encoded bytes of the frame.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.length]
+    }
+
+    /// Writes the frame to `stream` in one transaction.
+    #[inline]
+    pub fn write(&self, stream: Stream) {
+        stream.write_transaction(self.as_bytes());
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.buffer[self.length..self.length + bytes.len()].copy_from_slice(bytes);
+        self.length += bytes.len();
+    }
+
+    fn push_slice(&mut self, tag: u8, bytes: &[u8]) {
+        let available = MAX_FRAME_LENGTH.saturating_sub(self.length + 2);
+        let bytes = &bytes[..bytes.len().min(available).min(usize::from(u8::MAX))];
+        if self.length + 2 + bytes.len() <= MAX_FRAME_LENGTH {
+            self.push(&[tag, bytes.len() as u8]);
+            self.push(bytes);
+        }
+    }
+}
+
+macro_rules! impl_log_arg {
+    ($ty:ty, $tag:ident) => {
+        impl LogArg for $ty {
+            #[inline]
+            fn encode(&self, frame: &mut LogFrame) {
+                frame.push_tagged($tag, &self.to_le_bytes());
+            }
+        }
+    };
+}
+
+impl_log_arg!(u8, TAG_U8);
+impl_log_arg!(u16, TAG_U16);
+impl_log_arg!(u32, TAG_U32);
+impl_log_arg!(u64, TAG_U64);
+impl_log_arg!(i8, TAG_I8);
+impl_log_arg!(i16, TAG_I16);
+impl_log_arg!(i32, TAG_I32);
+impl_log_arg!(i64, TAG_I64);
+impl_log_arg!(f32, TAG_F32);
+impl_log_arg!(f64, TAG_F64);
+
+impl LogArg for usize {
+    #[inline]
+    fn encode(&self, frame: &mut LogFrame) {
+        (*self as u64).encode(frame);
+    }
+}
+
+impl LogArg for isize {
+    #[inline]
+    fn encode(&self, frame: &mut LogFrame) {
+        (*self as i64).encode(frame);
+    }
+}
+
+impl LogArg for bool {
+    #[inline]
+    fn encode(&self, frame: &mut LogFrame) {
+        frame.push_tagged(TAG_BOOL, &[u8::from(*self)]);
+    }
+}
+
+impl LogArg for char {
+    #[inline]
+    fn encode(&self, frame: &mut LogFrame) {
+        frame.push_tagged(TAG_CHAR, &u32::from(*self).to_le_bytes());
+    }
+}
+
+impl LogArg for str {
+    #[inline]
+    fn encode(&self, frame: &mut LogFrame) {
+        frame.push_slice(TAG_STR, self.as_bytes());
+    }
+}
+
+impl LogArg for [u8] {
+    #[inline]
+    fn encode(&self, frame: &mut LogFrame) {
+        frame.push_slice(TAG_BYTES, self);
+    }
+}
+
+impl<const N: usize> LogArg for [u8; N] {
+    #[inline]
+    fn encode(&self, frame: &mut LogFrame) {
+        frame.push_slice(TAG_BYTES, self);
+    }
+}
+
+impl<T: LogArg + ?Sized> LogArg for &T {
+    #[inline]
+    fn encode(&self, frame: &mut LogFrame) {
+        (**self).encode(frame);
+    }
+}
+
+#[doc(hidden)]
+pub const fn log_intern<const N: usize>(format: &str) -> [u8; N] {
+    let bytes = format.as_bytes();
+    let mut interned = [0; N];
+    let mut i = 0;
+    while i < N {
+        interned[i] = bytes[i];
+        i += 1;
+    }
+    interned
+}
+
+/// Host-side decoder for [`log!`](crate::stream_log!) frames.
+///
+/// The decoder is constructed from the contents of the [`LOG_SECTION`] link
+/// section of the application ELF file.
+#[cfg(feature = "host")]
+pub struct LogDecoder {
+    section: Vec<u8>,
+    base: u64,
+}
+
+/// Error returned by [`LogDecoder`].
+#[cfg(feature = "host")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LogDecodeError {
+    /// The ELF file is malformed or unsupported.
+    InvalidElf,
+    /// The ELF file doesn't contain the [`LOG_SECTION`] section.
+    MissingSection,
+    /// The frame is shorter than the format string index.
+    TruncatedFrame,
+    /// The frame refers to an unknown format string.
+    UnknownFormat(u32),
+    /// The frame contains an unknown argument type tag.
+    UnknownTag(u8),
+}
+
+#[cfg(feature = "host")]
+impl LogDecoder {
+    /// Creates a new decoder from the raw contents of the [`LOG_SECTION`]
+    /// section, which is located at the address `base`.
+    pub fn new(section: Vec<u8>, base: u64) -> Self {
+        Self { section, base }
+    }
+
+    /// Creates a new decoder from the contents of an ELF file.
+    ///
+    /// Both 32-bit and 64-bit little-endian ELF files are supported.
+    pub fn from_elf(elf: &[u8]) -> Result<Self, LogDecodeError> {
+        let (section, base) = elf_section(elf, LOG_SECTION)?;
+        Ok(Self::new(section.to_vec(), base))
+    }
+
+    /// Returns the format string with the given `index`.
+    pub fn format(&self, index: u32) -> Option<&str> {
+        let offset = usize::try_from(u64::from(index).checked_sub(self.base)?).ok()?;
+        let tail = self.section.get(offset..)?;
+        let length = tail.iter().position(|&byte| byte == 0)?;
+        str::from_utf8(&tail[..length]).ok()
+    }
+
+    /// Decodes a single frame into the formatted text.
+    pub fn decode(&self, frame: &[u8]) -> Result<String, LogDecodeError> {
+        let index = frame.get(..4).and_then(|index| index.try_into().ok());
+        let index = u32::from_le_bytes(index.ok_or(LogDecodeError::TruncatedFrame)?);
+        let format = self.format(index).ok_or(LogDecodeError::UnknownFormat(index))?;
+        let mut args = &frame[4..];
+        let mut output = String::new();
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    output.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    output.push('}');
+                }
+                '{' => {
+                    let mut spec = String::new();
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            break;
+                        }
+                        spec.push(c);
+                    }
+                    let spec = spec.split_once(':').map_or("", |(_, spec)| spec);
+                    if args.is_empty() {
+                        output.push_str("<truncated>");
+                    } else {
+                        decode_arg(&mut args, spec, &mut output)?;
+                    }
+                }
+                c => output.push(c),
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(feature = "host")]
+fn decode_arg(args: &mut &[u8], spec: &str, output: &mut String) -> Result<(), LogDecodeError> {
+    fn take<'a>(args: &mut &'a [u8], count: usize) -> &'a [u8] {
+        let count = count.min(args.len());
+        let (head, tail) = args.split_at(count);
+        *args = tail;
+        head
+    }
+    fn take_array<const N: usize>(args: &mut &[u8]) -> [u8; N] {
+        let mut array = [0; N];
+        let head = take(args, N);
+        array[..head.len()].copy_from_slice(head);
+        array
+    }
+    fn int(
+        output: &mut String,
+        spec: &str,
+        value: impl fmt::Display + fmt::LowerHex + fmt::UpperHex + fmt::Binary,
+    ) {
+        let _ = match spec.trim_start_matches('#') {
+            "x" if spec.starts_with('#') => write!(output, "{value:#x}"),
+            "x" => write!(output, "{value:x}"),
+            "X" if spec.starts_with('#') => write!(output, "{value:#X}"),
+            "X" => write!(output, "{value:X}"),
+            "b" if spec.starts_with('#') => write!(output, "{value:#b}"),
+            "b" => write!(output, "{value:b}"),
+            _ => write!(output, "{value}"),
+        };
+    }
+    let tag = take(args, 1)[0];
+    match tag {
+        TAG_U8 => int(output, spec, u8::from_le_bytes(take_array(args))),
+        TAG_U16 => int(output, spec, u16::from_le_bytes(take_array(args))),
+        TAG_U32 => int(output, spec, u32::from_le_bytes(take_array(args))),
+        TAG_U64 => int(output, spec, u64::from_le_bytes(take_array(args))),
+        TAG_I8 => int(output, spec, i8::from_le_bytes(take_array(args))),
+        TAG_I16 => int(output, spec, i16::from_le_bytes(take_array(args))),
+        TAG_I32 => int(output, spec, i32::from_le_bytes(take_array(args))),
+        TAG_I64 => int(output, spec, i64::from_le_bytes(take_array(args))),
+        TAG_F32 => {
+            let _ = write!(output, "{}", f32::from_le_bytes(take_array(args)));
+        }
+        TAG_F64 => {
+            let _ = write!(output, "{}", f64::from_le_bytes(take_array(args)));
+        }
+        TAG_BOOL => {
+            let _ = write!(output, "{}", take(args, 1).first().map_or(false, |&b| b != 0));
+        }
+        TAG_CHAR => {
+            let c = char::from_u32(u32::from_le_bytes(take_array(args)));
+            output.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+        TAG_STR => {
+            let length = take(args, 1).first().copied().unwrap_or(0);
+            let string = String::from_utf8_lossy(take(args, usize::from(length)));
+            let _ = if spec.ends_with('?') {
+                write!(output, "{string:?}")
+            } else {
+                write!(output, "{string}")
+            };
+        }
+        TAG_BYTES => {
+            let length = take(args, 1).first().copied().unwrap_or(0);
+            let _ = write!(output, "{:?}", take(args, usize::from(length)));
+        }
+        tag => return Err(LogDecodeError::UnknownTag(tag)),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "host")]
+fn elf_section<'a>(elf: &'a [u8], name: &str) -> Result<(&'a [u8], u64), LogDecodeError> {
+    fn read(elf: &[u8], offset: usize, size: usize) -> Result<u64, LogDecodeError> {
+        let bytes = slice(elf, offset, size)?;
+        let mut value = [0; 8];
+        value[..size].copy_from_slice(bytes);
+        Ok(u64::from_le_bytes(value))
+    }
+    fn slice(elf: &[u8], offset: usize, size: usize) -> Result<&[u8], LogDecodeError> {
+        let end = offset.checked_add(size).ok_or(LogDecodeError::InvalidElf)?;
+        elf.get(offset..end).ok_or(LogDecodeError::InvalidElf)
+    }
+    fn to_usize(value: u64) -> Result<usize, LogDecodeError> {
+        usize::try_from(value).map_err(|_| LogDecodeError::InvalidElf)
+    }
+    if elf.get(..4) != Some(b"\x7FELF") || elf.get(5) != Some(&1) {
+        return Err(LogDecodeError::InvalidElf);
+    }
+    // (e_shoff, e_shentsize, e_shnum, e_shstrndx, sh_addr, sh_offset, sh_size,
+    // word size)
+    let layout = match elf.get(4) {
+        Some(1) => (0x20, 0x2E, 0x30, 0x32, 0x0C, 0x10, 0x14, 4),
+        Some(2) => (0x28, 0x3A, 0x3C, 0x3E, 0x10, 0x18, 0x20, 8),
+        _ => return Err(LogDecodeError::InvalidElf),
+    };
+    let (shoff, shentsize, shnum, shstrndx, addr, offset, size, word) = layout;
+    let shoff = to_usize(read(elf, shoff, word)?)?;
+    let shentsize = to_usize(read(elf, shentsize, 2)?)?;
+    let shnum = to_usize(read(elf, shnum, 2)?)?;
+    let shstrndx = to_usize(read(elf, shstrndx, 2)?)?;
+    let field = |idx: usize, field: usize| {
+        idx.checked_mul(shentsize)
+            .and_then(|header| header.checked_add(shoff))
+            .and_then(|header| header.checked_add(field))
+            .ok_or(LogDecodeError::InvalidElf)
+    };
+    let section = |idx: usize| -> Result<(&'a [u8], u64), LogDecodeError> {
+        let addr = read(elf, field(idx, addr)?, word)?;
+        let offset = to_usize(read(elf, field(idx, offset)?, word)?)?;
+        let size = to_usize(read(elf, field(idx, size)?, word)?)?;
+        Ok((slice(elf, offset, size)?, addr))
+    };
+    let (names, _) = section(shstrndx)?;
+    for idx in 0..shnum {
+        let name_offset = to_usize(read(elf, field(idx, 0)?, 4)?)?;
+        let section_name = names.get(name_offset..).ok_or(LogDecodeError::InvalidElf)?;
+        let section_name = &section_name[..section_name.iter().position(|&b| b == 0).unwrap_or(0)];
+        if section_name == name.as_bytes() {
+            return section(idx);
+        }
+    }
+    Err(LogDecodeError::MissingSection)
+}
+
+#[cfg(feature = "host")]
+impl fmt::Display for LogDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidElf => write!(f, "invalid or unsupported ELF file"),
+            Self::MissingSection => write!(f, "missing `{LOG_SECTION}` section"),
+            Self::TruncatedFrame => write!(f, "truncated log frame"),
+            Self::UnknownFormat(index) => write!(f, "unknown format string index {index:#X}"),
+            Self::UnknownTag(tag) => write!(f, "unknown argument tag {tag:#04X}"),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    fn decoder() -> LogDecoder {
+        LogDecoder::new(b"hello\0{} + {:#x} = {}\0{:?} {{}} {}\0".to_vec(), 0x100)
+    }
+
+    #[test]
+    fn intern() {
+        const FORMAT: [u8; 4] = log_intern("abc\0");
+        assert_eq!(&FORMAT, b"abc\0");
+    }
+
+    #[test]
+    fn decode_plain() {
+        let frame = LogFrame::new(0x100);
+        assert_eq!(decoder().decode(frame.as_bytes()).unwrap(), "hello");
+    }
+
+    #[test]
+    fn decode_args() {
+        let mut frame = LogFrame::new(0x106);
+        1_u8.encode(&mut frame);
+        255_u32.encode(&mut frame);
+        (-3.5_f32).encode(&mut frame);
+        assert_eq!(decoder().decode(frame.as_bytes()).unwrap(), "1 + 0xff = -3.5");
+    }
+
+    #[test]
+    fn decode_str() {
+        let mut frame = LogFrame::new(0x116);
+        "x\"y".encode(&mut frame);
+        true.encode(&mut frame);
+        assert_eq!(decoder().decode(frame.as_bytes()).unwrap(), "\"x\\\"y\" {} true");
+    }
+
+    #[test]
+    fn decode_truncated() {
+        let mut frame = LogFrame::new(0x106);
+        1_u8.encode(&mut frame);
+        [0_u8; 300].encode(&mut frame);
+        (-1_i64).encode(&mut frame);
+        assert_eq!(frame.as_bytes().len(), MAX_FRAME_LENGTH);
+        assert_eq!(
+            decoder().decode(frame.as_bytes()).unwrap(),
+            format!("1 + {:?} = <truncated>", [0_u8; 247])
+        );
+    }
+
+    #[test]
+    fn elf_out_of_range() {
+        let mut elf = vec![0; 0x40];
+        elf[..6].copy_from_slice(b"\x7FELF\x02\x01");
+        elf[0x28..0x30].copy_from_slice(&u64::MAX.to_le_bytes());
+        elf[0x3A..0x3C].copy_from_slice(&0x40_u16.to_le_bytes());
+        elf[0x3C..0x3E].copy_from_slice(&1_u16.to_le_bytes());
+        assert_eq!(LogDecoder::from_elf(&elf).err(), Some(LogDecodeError::InvalidElf));
+        elf[0x28..0x30].copy_from_slice(&0x1000_u64.to_le_bytes());
+        assert_eq!(LogDecoder::from_elf(&elf).err(), Some(LogDecodeError::InvalidElf));
+    }
+
+    #[test]
+    fn decode_unknown() {
+        let frame = LogFrame::new(0x99);
+        assert_eq!(decoder().decode(frame.as_bytes()), Err(LogDecodeError::UnknownFormat(0x99)));
+    }
+}
@@ -181,3 +181,124 @@ macro_rules! dbg {
         ($($crate::dbg!($val)),+,)
     };
 }
+
+/// Writes a record with deferred formatting to a specific stream.
+///
+/// This is an alternative to [`print!`] with much lower run-time and flash
+/// costs. The format string is not included into the firmware image. Instead
+/// it is interned into the [`LOG_SECTION`](crate::stream::LOG_SECTION) link
+/// section, and only its index is written along with raw bytes of the
+/// arguments. Each argument must implement
+/// [`LogArg`](crate::stream::LogArg). The whole record is written in one
+/// transaction.
+///
+/// The record is written only if the stream is enabled by a debug probe.
+///
+/// # Examples
+///
+/// ```no_run
+/// use drone_core::stream;
+///
+/// let voltage = 3300_u16;
+/// stream::log!(2, "voltage = {} mV, ok = {}", voltage, true);
+/// ```
+#[macro_export]
+macro_rules! stream_log {
+    ($stream:expr, $format:literal $(, $arg:expr)* $(,)?) => {{
+        let stream = $crate::stream::Stream::new($stream);
+        if stream.is_enabled() {
+            const LENGTH: usize = $crate::_rt::core::concat!($format, "\0").len();
+            #[link_section = ".stream_log"]
+            static FORMAT: [u8; LENGTH] =
+                $crate::stream::log_intern($crate::_rt::core::concat!($format, "\0"));
+            let mut frame = $crate::stream::LogFrame::new(FORMAT.as_ptr() as usize as u32);
+            $($crate::stream::LogArg::encode(&$arg, &mut frame);)*
+            frame.write(stream);
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! stream_level {
+    ($level:ident, stream: $stream:expr, $($arg:tt)+) => {{
+        let stream = $stream;
+        if $crate::stream::Level::$level.is_enabled_in(
+            $crate::stream::Stream::new(stream),
+            $crate::_rt::core::module_path!(),
+        ) {
+            $crate::stream::write_record(
+                stream,
+                $crate::stream::Level::$level,
+                $crate::_rt::core::module_path!(),
+                $crate::_rt::core::format_args!($($arg)+),
+            );
+        }
+    }};
+    ($level:ident, $($arg:tt)+) => {
+        $crate::stream_level!($level, stream: $crate::stream::STDERR_STREAM, $($arg)+)
+    };
+}
+
+/// Writes a record at the [`Error`](crate::stream::Level::Error) level.
+///
+/// The record is written to the standard error (stream number 1), unless a
+/// different stream is given with the `stream:` prefix. The record is prefixed
+/// with the level and the module path of the invocation. See [the module level
+/// documentation](crate::stream#log-levels) for details on filtering.
+///
+/// # Examples
+///
+/// ```
+/// use drone_core::stream;
+///
+/// let code = 3;
+/// stream::error!("device failure: {}", code);
+/// stream::error!(stream: 5, "device failure: {}", code);
+/// ```
+#[macro_export]
+macro_rules! stream_error {
+    ($($arg:tt)+) => {
+        $crate::stream_level!(Error, $($arg)+)
+    };
+}
+
+/// Writes a record at the [`Warn`](crate::stream::Level::Warn) level.
+///
+/// See [`stream::error!`](crate::stream_error!) for details.
+#[macro_export]
+macro_rules! stream_warn {
+    ($($arg:tt)+) => {
+        $crate::stream_level!(Warn, $($arg)+)
+    };
+}
+
+/// Writes a record at the [`Info`](crate::stream::Level::Info) level.
+///
+/// See [`stream::error!`](crate::stream_error!) for details.
+#[macro_export]
+macro_rules! stream_info {
+    ($($arg:tt)+) => {
+        $crate::stream_level!(Info, $($arg)+)
+    };
+}
+
+/// Writes a record at the [`Debug`](crate::stream::Level::Debug) level.
+///
+/// See [`stream::error!`](crate::stream_error!) for details.
+#[macro_export]
+macro_rules! stream_debug {
+    ($($arg:tt)+) => {
+        $crate::stream_level!(Debug, $($arg)+)
+    };
+}
+
+/// Writes a record at the [`Trace`](crate::stream::Level::Trace) level.
+///
+/// See [`stream::error!`](crate::stream_error!) for details.
+#[macro_export]
+macro_rules! stream_trace {
+    ($($arg:tt)+) => {
+        $crate::stream_level!(Trace, $($arg)+)
+    };
+}
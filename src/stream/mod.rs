@@ -2,18 +2,105 @@
 //!
 //! This module implements standard output/error interface, which mimics Rust's
 //! standard library.
+//!
+//! # Deferred formatting
+//!
+//! [`print!`](crate::print) and friends format text on the target using
+//! [`core::fmt`], which is slow and takes a lot of flash space. The
+//! [`stream::log!`](crate::stream_log!) macro instead interns its format
+//! string into the [`LOG_SECTION`] link section and writes only the string
+//! index and raw argument bytes. The text is reconstructed on the host with
+//! `LogDecoder`, which is available under the `host` feature.
+//!
+//! # Log levels
+//!
+//! The [`stream::error!`](crate::stream_error!),
+//! [`stream::warn!`](crate::stream_warn!),
+//! [`stream::info!`](crate::stream_info!),
+//! [`stream::debug!`](crate::stream_debug!), and
+//! [`stream::trace!`](crate::stream_trace!) macros write records with a
+//! [`Level`]. Records above [`MAX_LEVEL`] are removed at compile-time. This is
+//! controlled by `max-level-*` cargo features. Records above the run-time
+//! level of the target stream are skipped. The run-time level defaults to
+//! [`DEFAULT_LEVEL`] and can be raised or lowered by a debug probe per stream
+//! without reflashing. The application can also set levels per module path
+//! prefix with [`set_module_levels`], which take precedence over the levels of
+//! streams.
+//!
+//! # Non-blocking writes
+//!
+//! [`Stream::write_transaction`] waits until the debug probe frees enough space
+//! in the ring buffer, which can stall the calling thread indefinitely if the
+//! probe is slow. [`Stream::write_async`] returns a future, which yields
+//! instead of waiting and is woken by [`poll_writers`]. For the cases where
+//! losing some output is preferable to any delay,
+//! [`Stream::try_write_transaction`] drops the bytes if the buffer is full.
+//!
+//! # Standard input
+//!
+//! The target can also receive bytes from the host. A debug probe writes them
+//! into a separate input ring buffer, and [`stdin`] reads them asynchronously
+//! through the [`io::Read`](crate::io::Read) interface. Waiting readers are
+//! woken by [`poll_stdin`].
+//!
+//! # RTT transport
+//!
+//! The [`stream!`](crate::stream!) macro can lay out a SEGGER RTT-compatible
+//! control block instead of the Drone Stream runtime, so that existing RTT
+//! probes and viewers work without Drone-specific tooling. See the [`rtt`]
+//! module for details.
+//!
+//! # Testing
+//!
+//! Under the `host` feature, the Drone Stream runtime is backed by an
+//! in-process buffer, and the [`host`] module provides a reader API to assert
+//! on the stream output in host tests.
 
 #![cfg_attr(feature = "host", allow(unused_imports, dead_code, unreachable_code, unused_variables))]
 
+#[cfg(feature = "host")]
+pub mod host;
+mod input;
+mod level;
+mod log;
 mod macros;
+mod record;
+pub mod rtt;
 mod runtime;
+mod writer;
 
-use self::runtime::{LocalGlobalRuntime, LocalRuntime};
-use crate::platform::stream_rt;
+pub use self::input::{poll_stdin, stdin, Stdin, INPUT_BUFFER_SIZE};
+pub use self::level::{set_module_levels, Level, DEFAULT_LEVEL, MAX_LEVEL};
+#[doc(hidden)]
+pub use self::log::log_intern;
+#[cfg(feature = "host")]
+pub use self::log::{LogDecodeError, LogDecoder};
+pub use self::log::{LogArg, LogFrame, LOG_SECTION};
+pub use self::record::StreamRecord;
+use self::runtime::LocalGlobalRuntime;
+pub use self::writer::{poll_writers, AsyncStreamWriter};
+use crate::platform::stream_rtt;
+/// Writes a record with deferred formatting to a specific stream.
+///
+/// See [`stream_log!`](crate::stream_log) for details.
+#[doc(no_inline)]
+pub use crate::stream_log as log;
+#[doc(no_inline)]
+pub use crate::{
+    stream_debug as debug, stream_error as error, stream_info as info, stream_trace as trace,
+    stream_warn as warn,
+};
 use core::cell::SyncUnsafeCell;
 use core::fmt::Write;
 use core::mem::size_of;
 use core::{fmt, mem, ptr};
+/// Derives [`StreamRecord`] for a `#[repr(C)]` struct.
+///
+/// The derive checks at compile-time that all fields implement
+/// [`StreamRecord`], and that the struct has no padding and fits into one
+/// transaction.
+#[doc(inline)]
+pub use drone_core_macros::StreamRecord;
 pub use drone_stream::STREAM_COUNT;
 use drone_stream::{GlobalRuntime, Runtime, BOOTSTRAP_SEQUENCE, BOOTSTRAP_SEQUENCE_LENGTH};
 
@@ -38,6 +125,9 @@ pub unsafe fn init(rt: *mut Runtime, buffer_size: u32, init_global: bool) {
     return unimplemented!();
     #[cfg(not(feature = "host"))]
     unsafe {
+        if init_global {
+            input::init();
+        }
         // Check if the debug probe wants to modify the runtime structure as
         // soon as possible.
         let mut buffer = rt.add(1).cast::<u8>();
@@ -53,6 +143,8 @@ pub unsafe fn init(rt: *mut Runtime, buffer_size: u32, init_global: bool) {
             // it, into the runtime structures.
             ptr::copy_nonoverlapping(buffer, rt.cast::<u8>(), mem::size_of::<Runtime>());
             buffer = buffer.add(mem::size_of::<Runtime>());
+            // The levels set by the debug probe before the bootstrap sequence
+            // are kept, only the global runtime is copied.
             if init_global {
                 ptr::copy_nonoverlapping(
                     buffer,
@@ -64,6 +156,7 @@ pub unsafe fn init(rt: *mut Runtime, buffer_size: u32, init_global: bool) {
             *rt.add(1).cast::<u8>() = 0;
         } else {
             if init_global {
+                level::init();
                 ptr::write_bytes(GLOBAL_RT.get().cast::<u8>(), 0, size_of::<GlobalRuntime>());
             }
             *rt = Runtime { buffer_size, read_cursor: 0, write_cursor: 0 };
@@ -71,6 +164,21 @@ pub unsafe fn init(rt: *mut Runtime, buffer_size: u32, init_global: bool) {
     }
 }
 
+/// Under the `host` feature, the global stream doesn't use the RTT transport,
+/// and this function does nothing.
+#[doc(hidden)]
+#[inline(never)]
+pub unsafe fn init_rtt(init_global: bool) {
+    #[cfg(not(feature = "host"))]
+    if init_global {
+        unsafe {
+            level::init();
+            input::init();
+            ptr::write_bytes(GLOBAL_RT.get().cast::<u8>(), 0, size_of::<GlobalRuntime>());
+        }
+    }
+}
+
 /// Returns a stream for the standard output.
 #[inline]
 pub fn stdout() -> Stream {
@@ -129,6 +237,17 @@ pub fn write_fmt(stream: u8, args: fmt::Arguments<'_>) {
     let _ = Stream::new(stream).write_fmt(args);
 }
 
+/// Writes a leveled record into a specific stream.
+///
+/// The record is prefixed with `level` and `module`, and terminated with a
+/// newline. This function doesn't check whether the level is enabled. It's
+/// recommended to use this function in conjunction with [`Level::is_enabled`].
+#[inline(never)]
+#[export_name = "stream_write_record"]
+pub fn write_record(stream: u8, level: Level, module: &str, args: fmt::Arguments<'_>) {
+    let _ = Stream::new(stream).write_fmt(format_args!("[{level:<5} {module}] {args}\n"));
+}
+
 impl Stream {
     /// Creates a new stream handle.
     ///
@@ -143,9 +262,15 @@ impl Stream {
 
     /// Returns `true` if this stream is explicitly enabled by a debug probe in
     /// the run-time, returns `false` by default.
+    ///
+    /// With the RTT transport, returns `true` if there is an up channel for
+    /// this stream.
     #[inline]
     pub fn is_enabled(self) -> bool {
         let Self(stream) = self;
+        if let Some(rtt) = stream_rtt() {
+            return unsafe { rtt::is_enabled(rtt, stream) };
+        }
         unsafe { (*GLOBAL_RT.get()).is_enabled(stream) }
     }
 
@@ -158,7 +283,7 @@ impl Stream {
     #[inline]
     pub fn write_bytes(self, bytes: &[u8]) -> Self {
         let Self(stream) = self;
-        unsafe { (*stream_rt()).write_bytes(stream, bytes.as_ptr(), bytes.len()) };
+        unsafe { runtime::write_bytes(stream, bytes.as_ptr(), bytes.len()) };
         self
     }
 
@@ -172,17 +297,24 @@ impl Stream {
     pub fn write_transaction(self, bytes: &[u8]) -> Self {
         let Self(stream) = self;
         let length = bytes.len().try_into().expect("maximum transaction length exceeded");
-        unsafe { (*stream_rt()).write_transaction(stream, bytes.as_ptr(), length) };
+        unsafe { runtime::write_transaction(stream, bytes.as_ptr(), length) };
         self
     }
 
     /// Writes `T` as a sequence of bytes to this stream in one transaction.
-    /// `T` can be one of  `u8`, `u16`, `u32`.
+    /// `T` can be an integer, a float, an array, or a struct deriving
+    /// [`StreamRecord`].
+    ///
+    /// # Panics
+    ///
+    /// If size of `T` is more than 255 bytes.
     #[allow(clippy::return_self_not_must_use)]
     #[inline]
-    pub fn write<T: sealed::StreamWrite>(self, value: T) -> Self {
+    pub fn write<T: StreamRecord>(self, value: T) -> Self {
         let Self(stream) = self;
-        T::stream_write(stream, value);
+        let length = size_of::<T>().try_into().expect("maximum transaction length exceeded");
+        let buffer = ptr::addr_of!(value).cast::<u8>();
+        unsafe { runtime::write_transaction(stream, buffer, length) };
         self
     }
 }
@@ -194,29 +326,3 @@ impl Write for Stream {
         Ok(())
     }
 }
-
-mod sealed {
-    use super::{stream_rt, LocalRuntime};
-
-    pub trait StreamWrite: Copy {
-        fn stream_write(stream: u8, value: Self);
-    }
-
-    macro_rules! impl_integer {
-        ($ty:ty) => {
-            impl StreamWrite for $ty {
-                #[inline]
-                fn stream_write(stream: u8, value: Self) {
-                    let bytes = value.to_ne_bytes();
-                    unsafe {
-                        (*stream_rt()).write_transaction(stream, bytes.as_ptr(), bytes.len() as u8);
-                    }
-                }
-            }
-        };
-    }
-
-    impl_integer!(u8);
-    impl_integer!(u16);
-    impl_integer!(u32);
-}
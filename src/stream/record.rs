@@ -0,0 +1,41 @@
+/// A value, which can be written to a stream as its in-memory representation
+/// in one transaction.
+///
+/// The trait is implemented for integers, floats, and arrays of such values.
+/// It can be derived for a `#[repr(C)]` struct, which fields are all
+/// `StreamRecord`, to emit structured samples without packing bytes by hand:
+///
+/// ```
+/// use drone_core::stream::{Stream, StreamRecord};
+///
+/// #[derive(Clone, Copy, StreamRecord)]
+/// #[repr(C)]
+/// struct Sample {
+///     timestamp: u32,
+///     voltage: f32,
+///     current: [i16; 2],
+/// }
+///
+/// let sample = Sample { timestamp: 1, voltage: 3.3, current: [-1, 1] };
+/// if Stream::new(11).is_enabled() {
+///     Stream::new(11).write(sample);
+/// }
+/// ```
+///
+/// The bytes are written in the native byte order of the target.
+///
+/// # Safety
+///
+/// The type must not contain padding or pointers, so that every byte of its
+/// in-memory representation is initialized and meaningful to the host.
+pub unsafe trait StreamRecord: Copy {}
+
+macro_rules! impl_primitive {
+    ($($ty:ty),*) => {
+        $(unsafe impl StreamRecord for $ty {})*
+    };
+}
+
+impl_primitive!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+unsafe impl<T: StreamRecord, const N: usize> StreamRecord for [T; N] {}
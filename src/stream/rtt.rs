@@ -0,0 +1,374 @@
+//! SEGGER RTT-compatible transport.
+//!
+//! Instead of the Drone Stream runtime structure, the [`stream!`] macro can lay
+//! out an RTT control block with up (target-to-host) and down (host-to-target)
+//! buffers:
+//!
+//! ```ignore
+//! stream! {
+//!     layout => core0;
+//!     metadata => pub Stream;
+//!     instance => pub STREAM;
+//!     global => true;
+//!     transport => rtt(up: 2, down: 1);
+//! }
+//! ```
+//!
+//! Stream numbers map directly to up channel numbers, and [`stdin`] reads
+//! from the down channel `0`. A stream is considered enabled if there is an up
+//! channel for it, because RTT probes don't manage an enable mask. The down
+//! buffers are [`DOWN_BUFFER_SIZE`] bytes each, and the remaining space of the
+//! layout section is divided equally between the up buffers.
+//!
+//! The global stream uses the RTT transport when the `rtt` feature is enabled,
+//! and the [`stream!`] macro checks at compile-time that its `global` stream
+//! agrees with the feature. Under the `host` feature, the in-process Drone
+//! Stream runtime is used regardless.
+//!
+//! What happens if an up buffer doesn't have enough free space is controlled by
+//! the channel mode, which can be changed by the host:
+//!
+//! * [`MODE_NO_BLOCK_SKIP`] (the default) drops the whole write.
+//! * [`MODE_NO_BLOCK_TRIM`] writes as many bytes as fit, and drops the rest.
+//! * [`MODE_BLOCK_IF_FIFO_FULL`] waits for the host to free enough space. A
+//!   transaction longer than the up buffer is dropped, while
+//!   [`Stream::write_bytes`] splits the bytes into chunks which fit into the
+//!   free space.
+//!
+//! [`stream!`]: crate::stream!
+//! [`stdin`]: super::stdin
+//! [`Stream::write_bytes`]: super::Stream::write_bytes
+
+use crate::platform::Interrupts;
+use core::sync::atomic::{compiler_fence, Ordering};
+use core::{ptr, slice};
+
+/// `true` if the global stream uses the RTT transport.
+pub const GLOBAL: bool = cfg!(feature = "rtt");
+
+/// Identifier, which RTT probes look for in RAM to locate the control block.
+pub const ID: [u8; 16] = *b"SEGGER RTT\0\0\0\0\0\0";
+
+/// Size of each down buffer in bytes.
+pub const DOWN_BUFFER_SIZE: usize = 16;
+
+/// Drop the data if it doesn't fit into the buffer.
+pub const MODE_NO_BLOCK_SKIP: u32 = 0;
+
+/// Write as much data as fits into the buffer and drop the rest.
+pub const MODE_NO_BLOCK_TRIM: u32 = 1;
+
+/// Wait until there is enough space in the buffer.
+pub const MODE_BLOCK_IF_FIFO_FULL: u32 = 2;
+
+const MODE_MASK: u32 = 0b11;
+
+/// Header of the RTT control block.
+#[repr(C)]
+pub struct Header {
+    id: [u8; 16],
+    max_up_buffers: i32,
+    max_down_buffers: i32,
+}
+
+/// Descriptor of an RTT ring buffer.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct Buffer {
+    name: *const u8,
+    buffer: *mut u8,
+    size: u32,
+    write_offset: u32,
+    read_offset: u32,
+    flags: u32,
+}
+
+/// RTT control block with `UP` up buffers and `DOWN` down buffers.
+#[repr(C)]
+pub struct ControlBlock<const UP: usize, const DOWN: usize> {
+    header: Header,
+    up: [Buffer; UP],
+    down: [Buffer; DOWN],
+}
+
+unsafe impl<const UP: usize, const DOWN: usize> Sync for ControlBlock<UP, DOWN> {}
+
+impl Buffer {
+    const fn zeroed() -> Self {
+        Self {
+            name: ptr::null(),
+            buffer: ptr::null_mut(),
+            size: 0,
+            write_offset: 0,
+            read_offset: 0,
+            flags: 0,
+        }
+    }
+}
+
+impl<const UP: usize, const DOWN: usize> ControlBlock<UP, DOWN> {
+    /// Creates a new zeroed control block.
+    ///
+    /// Probes don't recognize a zeroed control block until
+    /// [`ControlBlock::init`] is called.
+    #[must_use]
+    #[inline]
+    pub const fn zeroed() -> Self {
+        Self {
+            header: Header { id: [0; 16], max_up_buffers: 0, max_down_buffers: 0 },
+            up: [Buffer::zeroed(); UP],
+            down: [Buffer::zeroed(); DOWN],
+        }
+    }
+
+    /// Initializes the control block at `this` with `UP` consecutive up
+    /// buffers of `up_size` bytes starting at `up_buffers`, and `DOWN`
+    /// consecutive down buffers of `down_size` bytes starting at
+    /// `down_buffers`.
+    ///
+    /// The identifier is written last, so a probe scanning the memory never
+    /// sees a partially initialized control block.
+    ///
+    /// # Safety
+    ///
+    /// All pointers must be valid for the given sizes. This function may
+    /// corrupt any on-going transmissions.
+    pub unsafe fn init(
+        this: *mut Self,
+        up_buffers: *mut u8,
+        up_size: u32,
+        down_buffers: *mut u8,
+        down_size: u32,
+    ) {
+        unsafe {
+            ptr::addr_of_mut!((*this).header.id).write_volatile([0; 16]);
+            compiler_fence(Ordering::SeqCst);
+            for i in 0..UP {
+                ptr::addr_of_mut!((*this).up[i]).write_volatile(Buffer {
+                    buffer: up_buffers.add(i * up_size as usize),
+                    size: up_size,
+                    ..Buffer::zeroed()
+                });
+            }
+            for i in 0..DOWN {
+                ptr::addr_of_mut!((*this).down[i]).write_volatile(Buffer {
+                    buffer: down_buffers.add(i * down_size as usize),
+                    size: down_size,
+                    ..Buffer::zeroed()
+                });
+            }
+            ptr::addr_of_mut!((*this).header.max_up_buffers).write_volatile(UP as i32);
+            ptr::addr_of_mut!((*this).header.max_down_buffers).write_volatile(DOWN as i32);
+            compiler_fence(Ordering::SeqCst);
+            ptr::addr_of_mut!((*this).header.id).write_volatile(ID);
+        }
+    }
+
+    /// Returns a type-erased pointer to the control block at `this`.
+    #[inline]
+    pub fn header(this: *mut Self) -> *mut Header {
+        this.cast()
+    }
+}
+
+/// Returns `true` if there is an up channel for `stream`.
+pub(crate) unsafe fn is_enabled(header: *mut Header, stream: u8) -> bool {
+    unsafe { up_buffer(header, stream).is_some() }
+}
+
+/// Writes `length` bytes from `source` to the up channel `stream` at once.
+/// Returns `false` if the bytes were dropped.
+///
+/// If `wait` is `true` and the channel is in [`MODE_BLOCK_IF_FIFO_FULL`] mode,
+/// waits for enough free space, unless `length` exceeds the buffer capacity.
+pub(crate) unsafe fn write(
+    header: *mut Header,
+    stream: u8,
+    source: *const u8,
+    length: usize,
+    wait: bool,
+) -> bool {
+    let up = match unsafe { up_buffer(header, stream) } {
+        Some(up) => up,
+        None => return false,
+    };
+    loop {
+        let mode = unsafe { ptr::addr_of!((*up).flags).read_volatile() } & MODE_MASK;
+        let trim = mode == MODE_NO_BLOCK_TRIM;
+        if Interrupts::paused(|| unsafe { put(up, source, length, trim) }) {
+            return true;
+        }
+        if !wait || mode != MODE_BLOCK_IF_FIFO_FULL || length > unsafe { capacity(up) } {
+            return false;
+        }
+    }
+}
+
+/// Writes `length` bytes from `source` to the up channel `stream`.
+///
+/// In [`MODE_BLOCK_IF_FIFO_FULL`] mode, the bytes are written in chunks as the
+/// free space allows. In other modes, behaves like [`write`].
+pub(crate) unsafe fn write_bytes(
+    header: *mut Header,
+    stream: u8,
+    mut source: *const u8,
+    mut length: usize,
+) {
+    let up = match unsafe { up_buffer(header, stream) } {
+        Some(up) => up,
+        None => return,
+    };
+    while length > 0 {
+        let mode = unsafe { ptr::addr_of!((*up).flags).read_volatile() } & MODE_MASK;
+        if mode != MODE_BLOCK_IF_FIFO_FULL {
+            let trim = mode == MODE_NO_BLOCK_TRIM;
+            Interrupts::paused(|| unsafe { put(up, source, length, trim) });
+            return;
+        }
+        let count = Interrupts::paused(|| unsafe {
+            let count = length.min(available(up));
+            copy(up, source, count);
+            count
+        });
+        source = unsafe { source.add(count) };
+        length -= count;
+    }
+}
+
+/// Reads available bytes from the down channel `channel` into `buf`, returning
+/// the number of bytes read.
+pub(crate) unsafe fn read(header: *mut Header, channel: u8, buf: &mut [u8]) -> usize {
+    unsafe {
+        let max_down = ptr::addr_of!((*header).max_down_buffers).read_volatile();
+        if i32::from(channel) >= max_down {
+            return 0;
+        }
+        let max_up = ptr::addr_of!((*header).max_up_buffers).read_volatile();
+        let down = buffers(header).add(max_up as usize + usize::from(channel));
+        let size = ptr::addr_of!((*down).size).read_volatile();
+        let buffer = ptr::addr_of!((*down).buffer).read_volatile();
+        let write_offset = ptr::addr_of!((*down).write_offset).read_volatile();
+        let mut read_offset = ptr::addr_of!((*down).read_offset).read_volatile();
+        let mut count = 0;
+        while count < buf.len() && read_offset != write_offset {
+            buf[count] = buffer.add(read_offset as usize).read_volatile();
+            read_offset = (read_offset + 1) % size;
+            count += 1;
+        }
+        ptr::addr_of_mut!((*down).read_offset).write_volatile(read_offset);
+        count
+    }
+}
+
+unsafe fn buffers(header: *mut Header) -> *mut Buffer {
+    unsafe { header.add(1).cast() }
+}
+
+unsafe fn up_buffer(header: *mut Header, stream: u8) -> Option<*mut Buffer> {
+    unsafe {
+        let max_up = ptr::addr_of!((*header).max_up_buffers).read_volatile();
+        (i32::from(stream) < max_up).then(|| buffers(header).add(usize::from(stream)))
+    }
+}
+
+unsafe fn capacity(up: *mut Buffer) -> usize {
+    unsafe { ptr::addr_of!((*up).size).read_volatile() as usize - 1 }
+}
+
+unsafe fn available(up: *mut Buffer) -> usize {
+    unsafe {
+        let size = ptr::addr_of!((*up).size).read_volatile() as usize;
+        let read_offset = ptr::addr_of!((*up).read_offset).read_volatile() as usize;
+        let write_offset = ptr::addr_of!((*up).write_offset).read_volatile() as usize;
+        if read_offset > write_offset {
+            read_offset - write_offset - 1
+        } else {
+            size - 1 - write_offset + read_offset
+        }
+    }
+}
+
+unsafe fn put(up: *mut Buffer, source: *const u8, length: usize, trim: bool) -> bool {
+    unsafe {
+        let available = available(up);
+        let length = if length <= available {
+            length
+        } else if trim && available > 0 {
+            available
+        } else {
+            return false;
+        };
+        copy(up, source, length);
+        true
+    }
+}
+
+unsafe fn copy(up: *mut Buffer, source: *const u8, length: usize) {
+    unsafe {
+        let size = ptr::addr_of!((*up).size).read_volatile() as usize;
+        let buffer = ptr::addr_of!((*up).buffer).read_volatile();
+        let write_offset = ptr::addr_of!((*up).write_offset).read_volatile() as usize;
+        let source = slice::from_raw_parts(source, length);
+        let (head, tail) = source.split_at(length.min(size - write_offset));
+        buffer.add(write_offset).copy_from_nonoverlapping(head.as_ptr(), head.len());
+        buffer.copy_from_nonoverlapping(tail.as_ptr(), tail.len());
+        compiler_fence(Ordering::SeqCst);
+        let write_offset = (write_offset + length) % size;
+        ptr::addr_of_mut!((*up).write_offset).write_volatile(write_offset as u32);
+    }
+}
+
+#[cfg(all(test, feature = "host"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read() {
+        let mut cb = ControlBlock::<1, 1>::zeroed();
+        let mut up = [0_u8; 8];
+        let mut down = [0_u8; 4];
+        let header = ControlBlock::header(ptr::addr_of_mut!(cb));
+        unsafe {
+            ControlBlock::init(ptr::addr_of_mut!(cb), up.as_mut_ptr(), 8, down.as_mut_ptr(), 4);
+            assert!(is_enabled(header, 0));
+            assert!(!is_enabled(header, 1));
+            assert!(write(header, 0, b"hello".as_ptr(), 5, false));
+            assert!(!write(header, 0, b"abc".as_ptr(), 3, false));
+            cb.up[0].flags = MODE_NO_BLOCK_TRIM;
+            assert!(write(header, 0, b"abc".as_ptr(), 3, false));
+        }
+        assert_eq!(cb.header.id, ID);
+        assert_eq!(&up[..7], b"helloab");
+        assert_eq!(cb.up[0].write_offset, 7);
+        cb.up[0].read_offset = 7;
+        assert!(unsafe { write(header, 0, b"xyz".as_ptr(), 3, false) });
+        assert_eq!(up[7], b'x');
+        assert_eq!(&up[..2], b"yz");
+        assert_eq!(cb.up[0].write_offset, 2);
+        down[..3].copy_from_slice(b"cmd");
+        cb.down[0].write_offset = 3;
+        let mut buf = [0; 8];
+        assert_eq!(unsafe { read(header, 0, &mut buf) }, 3);
+        assert_eq!(&buf[..3], b"cmd");
+        assert_eq!(cb.down[0].read_offset, 3);
+    }
+
+    #[test]
+    fn block_oversized() {
+        let mut cb = ControlBlock::<1, 0>::zeroed();
+        let mut up = [0_u8; 8];
+        let header = ControlBlock::header(ptr::addr_of_mut!(cb));
+        unsafe {
+            ControlBlock::init(ptr::addr_of_mut!(cb), up.as_mut_ptr(), 8, ptr::null_mut(), 0);
+            cb.up[0].flags = MODE_BLOCK_IF_FIFO_FULL;
+            assert!(!write(header, 0, b"overflow".as_ptr(), 8, true));
+            assert_eq!(cb.up[0].write_offset, 0);
+            cb.up[0].read_offset = 5;
+            cb.up[0].write_offset = 5;
+            write_bytes(header, 0, b"chunk".as_ptr(), 5);
+        }
+        assert_eq!(cb.up[0].write_offset, 2);
+        assert_eq!(&up[5..], b"chu");
+        assert_eq!(&up[..2], b"nk");
+    }
+}
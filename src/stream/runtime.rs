@@ -1,10 +1,11 @@
 #![cfg_attr(feature = "host", allow(unused_imports, unused_variables))]
 
-use crate::platform::Interrupts;
+use super::rtt;
+use crate::platform::{stream_rt, stream_rtt, Interrupts};
 use core::ptr;
 use drone_stream::{GlobalRuntime, Runtime, HEADER_LENGTH};
 
-const DEFAULT_TRANSACTION_LENGTH: u8 = 64;
+pub(super) const DEFAULT_TRANSACTION_LENGTH: u8 = 64;
 
 pub trait LocalGlobalRuntime {
     fn is_enabled(&self, stream: u8) -> bool;
@@ -14,6 +15,8 @@ pub trait LocalRuntime {
     unsafe fn write_bytes(&mut self, stream: u8, buffer: *const u8, length: usize);
 
     unsafe fn write_transaction(&mut self, stream: u8, buffer: *const u8, length: u8);
+
+    unsafe fn try_write_transaction(&mut self, stream: u8, buffer: *const u8, length: u8) -> bool;
 }
 
 impl LocalGlobalRuntime for GlobalRuntime {
@@ -40,26 +43,93 @@ impl LocalRuntime for Runtime {
     #[export_name = "stream_write_transaction"]
     unsafe fn write_transaction(&mut self, stream: u8, buffer: *const u8, length: u8) {
         #[cfg(feature = "host")]
-        return unimplemented!();
+        loop {
+            if unsafe { transaction(self, stream, buffer, length).write() } {
+                break;
+            }
+            // There is no debug probe on the host, read the ring buffer
+            // ourselves to free some space.
+            super::host::drain();
+        }
         #[cfg(not(feature = "host"))]
         loop {
-            let complete = Interrupts::paused(|| unsafe {
-                let transaction = Transaction {
-                    buffer: ptr::addr_of_mut!(*self).add(1).cast::<u8>(),
-                    buffer_size: self.buffer_size,
-                    write_cursor: ptr::addr_of_mut!(self.write_cursor),
-                    read_cursor: ptr::addr_of!(self.read_cursor),
-                    stream,
-                    source: buffer,
-                    source_size: length,
-                };
-                transaction.write()
-            });
+            let complete =
+                Interrupts::paused(|| unsafe { transaction(self, stream, buffer, length).write() });
             if complete {
                 break;
             }
         }
     }
+
+    #[inline(never)]
+    #[export_name = "stream_try_write_transaction"]
+    unsafe fn try_write_transaction(&mut self, stream: u8, buffer: *const u8, length: u8) -> bool {
+        #[cfg(feature = "host")]
+        return unsafe { try_write(self, stream, buffer, length) };
+        #[cfg(not(feature = "host"))]
+        return Interrupts::paused(|| unsafe { try_write(self, stream, buffer, length) });
+    }
+}
+
+/// Writes a sequence of bytes to `stream` through the transport of the global
+/// stream.
+#[inline]
+pub(super) unsafe fn write_bytes(stream: u8, buffer: *const u8, length: usize) {
+    if let Some(rtt) = stream_rtt() {
+        unsafe { rtt::write_bytes(rtt, stream, buffer, length) };
+    } else {
+        unsafe { (*stream_rt()).write_bytes(stream, buffer, length) };
+    }
+}
+
+/// Writes a sequence of bytes to `stream` in one transaction through the
+/// transport of the global stream.
+#[inline]
+pub(super) unsafe fn write_transaction(stream: u8, buffer: *const u8, length: u8) {
+    if let Some(rtt) = stream_rtt() {
+        unsafe { rtt::write(rtt, stream, buffer, usize::from(length), true) };
+    } else {
+        unsafe { (*stream_rt()).write_transaction(stream, buffer, length) };
+    }
+}
+
+/// Tries to write a sequence of bytes to `stream` in one transaction through
+/// the transport of the global stream, without waiting for free space.
+#[inline]
+pub(super) unsafe fn try_write_transaction(stream: u8, buffer: *const u8, length: u8) -> bool {
+    if let Some(rtt) = stream_rtt() {
+        unsafe { rtt::write(rtt, stream, buffer, usize::from(length), false) }
+    } else {
+        unsafe { (*stream_rt()).try_write_transaction(stream, buffer, length) }
+    }
+}
+
+#[inline]
+unsafe fn try_write(runtime: &mut Runtime, stream: u8, source: *const u8, source_size: u8) -> bool {
+    // The first attempt may only put a wrap marker at the end of the buffer, in
+    // which case the second attempt writes from the beginning.
+    unsafe {
+        transaction(runtime, stream, source, source_size).write()
+            || transaction(runtime, stream, source, source_size).write()
+    }
+}
+
+#[inline]
+unsafe fn transaction(
+    runtime: &mut Runtime,
+    stream: u8,
+    source: *const u8,
+    source_size: u8,
+) -> Transaction {
+    Transaction {
+        buffer: unsafe { ptr::addr_of_mut!(*runtime).add(1).cast::<u8>() },
+        buffer_size: runtime.buffer_size,
+        write_cursor: ptr::addr_of_mut!(runtime.write_cursor),
+        read_cursor: ptr::addr_of!(runtime.read_cursor),
+        stream,
+        source,
+        source_size,
+    }
 }
 
 struct Transaction {
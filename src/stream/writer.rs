@@ -0,0 +1,156 @@
+use super::runtime::{self, DEFAULT_TRANSACTION_LENGTH};
+use super::Stream;
+use crate::platform::Interrupts;
+use crate::sync::LinkedList;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::task::{Context, Poll, Waker};
+
+#[cfg(not(loom))]
+static WAITERS: LinkedList<Waker> = LinkedList::new();
+#[cfg(loom)]
+loom::lazy_static! {
+    static ref WAITERS: LinkedList<Waker> = LinkedList::new();
+}
+
+/// Incremented each time [`WAITERS`] is emptied by [`poll_writers`].
+static EPOCH: AtomicU32 = AtomicU32::new(0);
+
+/// Future returned by [`Stream::write_async`].
+///
+/// The future writes the bytes in transactions of at most 64 bytes. If the
+/// ring buffer is full, the future registers its waker and returns
+/// [`Poll::Pending`] instead of spinning. The waker is woken by the next
+/// [`poll_writers`] call. The future keeps at most one waker registered, and
+/// removes it when dropped.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct AsyncStreamWriter<'a> {
+    stream: u8,
+    bytes: &'a [u8],
+    registered: Option<(Waker, u32)>,
+}
+
+/// Wakes all [`AsyncStreamWriter`] futures waiting for free space in a ring
+/// buffer.
+///
+/// This is the polling hook for asynchronous stream writes. It should be called
+/// periodically, e.g. from a timer interrupt handler, to give the debug probe a
+/// chance to drain the buffers between attempts.
+#[inline(never)]
+#[export_name = "stream_poll_writers"]
+pub fn poll_writers() {
+    let waiters = LinkedList::new();
+    Interrupts::paused(|| unsafe {
+        while let Some(node) = WAITERS.pop_raw() {
+            waiters.push_raw(node);
+        }
+        EPOCH.store(EPOCH.load(Ordering::Relaxed).wrapping_add(1), Ordering::Relaxed);
+    });
+    for waker in waiters {
+        waker.wake();
+    }
+}
+
+impl Stream {
+    /// Writes a sequence of bytes to this stream without blocking.
+    ///
+    /// The returned future resolves when all bytes are written. See
+    /// [`AsyncStreamWriter`] for details.
+    #[inline]
+    pub fn write_async(self, bytes: &[u8]) -> AsyncStreamWriter<'_> {
+        let Self(stream) = self;
+        AsyncStreamWriter { stream, bytes, registered: None }
+    }
+
+    /// Tries to write a sequence of bytes to this stream in one transaction.
+    ///
+    /// Unlike [`Stream::write_transaction`], this method never waits for free
+    /// space in the ring buffer. If the buffer is full, the bytes are dropped
+    /// and `false` is returned.
+    ///
+    /// # Panics
+    ///
+    /// If length of `bytes` is more than 256.
+    #[inline]
+    pub fn try_write_transaction(self, bytes: &[u8]) -> bool {
+        let Self(stream) = self;
+        let length = bytes.len().try_into().expect("maximum transaction length exceeded");
+        unsafe { runtime::try_write_transaction(stream, bytes.as_ptr(), length) }
+    }
+}
+
+impl AsyncStreamWriter<'_> {
+    fn try_write(&mut self) -> bool {
+        while !self.bytes.is_empty() {
+            let length = self.bytes.len().min(usize::from(DEFAULT_TRANSACTION_LENGTH));
+            let (chunk, rest) = self.bytes.split_at(length);
+            if !Stream(self.stream).try_write_transaction(chunk) {
+                return false;
+            }
+            self.bytes = rest;
+        }
+        true
+    }
+
+    fn register(&mut self, waker: &Waker) {
+        Interrupts::paused(|| {
+            let epoch = EPOCH.load(Ordering::Relaxed);
+            if let Some((registered, registered_epoch)) = &self.registered {
+                if *registered_epoch == epoch {
+                    if registered.will_wake(waker) {
+                        return;
+                    }
+                    unsafe { remove_waiter(registered) };
+                }
+            }
+            WAITERS.push(waker.clone());
+            self.registered = Some((waker.clone(), epoch));
+        });
+    }
+
+    fn deregister(&mut self) {
+        if let Some((registered, registered_epoch)) = self.registered.take() {
+            Interrupts::paused(|| {
+                // If the epoch has changed, the waker is already removed by
+                // `poll_writers`.
+                if registered_epoch == EPOCH.load(Ordering::Relaxed) {
+                    unsafe { remove_waiter(&registered) };
+                }
+            });
+        }
+    }
+}
+
+impl Future for AsyncStreamWriter<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if !self.try_write() {
+            self.register(cx.waker());
+            // The buffer could have been drained between the attempt and the
+            // registration.
+            if !self.try_write() {
+                return Poll::Pending;
+            }
+        }
+        self.deregister();
+        Poll::Ready(())
+    }
+}
+
+impl Drop for AsyncStreamWriter<'_> {
+    fn drop(&mut self) {
+        self.deregister();
+    }
+}
+
+/// Removes one waker, which wakes the same task as `waker`, from
+/// [`WAITERS`]. Must be called inside a critical section, so that it doesn't
+/// race with other removals.
+unsafe fn remove_waiter(waker: &Waker) {
+    let node = unsafe { WAITERS.drain_filter_raw(|node| (*node).will_wake(waker)).next() };
+    if let Some(node) = node {
+        drop(unsafe { Box::from_raw(node.cast_mut()) });
+    }
+}
@@ -1,10 +1,15 @@
 use crate::fib;
 use crate::thr::prelude::*;
-use core::fmt::Display;
-use core::future::Future;
+use core::fmt::{self, Display};
+use core::future::{self, Future, Ready};
 use core::pin::Pin;
 use core::task::{Context, Poll, Waker};
 
+#[cfg(any(feature = "atomics", loom))]
+type CounterState = core::sync::atomic::AtomicU32;
+#[cfg(not(any(feature = "atomics", loom)))]
+type CounterState = crate::sync::soft_atomic::Atomic<u32>;
+
 /// Thread executor.
 pub trait ThrExec: ThrToken {
     /// Wakes up the thread.
@@ -37,6 +42,22 @@ pub trait ThrExec: ThrToken {
         self.wakeup();
     }
 
+    /// Adds an executor for the future `fut` to the fiber chain and wakes up
+    /// the thread immediately. An error returned by the future is passed to
+    /// `handler`.
+    ///
+    /// Unlike [`ThrExec::exec`], an error doesn't cause a panic, so a failing
+    /// task can be logged and restarted without taking down the whole program.
+    #[inline]
+    fn exec_with_handler<F, E, H>(self, fut: F, handler: H)
+    where
+        F: Future<Output = Result<(), E>> + Send + 'static,
+        H: FnOnce(E) + Send + 'static,
+    {
+        self.add_exec_with_handler(fut, handler);
+        self.wakeup();
+    }
+
     /// Adds an executor for the future `fut` to the fiber chain.
     ///
     /// The future `fut` will start polling on the next thread wake-up.
@@ -49,6 +70,54 @@ pub trait ThrExec: ThrToken {
         self.add_exec_factory(|| fut);
     }
 
+    /// Adds an executor for the future `fut` to the fiber chain. An error
+    /// returned by the future is passed to `handler`.
+    ///
+    /// The future `fut` will start polling on the next thread wake-up. See
+    /// also [`ThrExec::exec_with_handler`].
+    #[inline]
+    fn add_exec_with_handler<F, E, H>(self, fut: F, handler: H)
+    where
+        F: Future<Output = Result<(), E>> + Send + 'static,
+        H: FnOnce(E) + Send + 'static,
+    {
+        self.add_exec(async move {
+            if let Err(err) = fut.await {
+                handler(err);
+            }
+        });
+    }
+
+    /// Adds a supervised task, which runs the future returned by `factory`,
+    /// to the fiber chain and wakes up the thread immediately.
+    ///
+    /// When the future returns an error, the error is passed to `policy`,
+    /// which decides whether the task is restarted with a new future from
+    /// `factory`, and how long to wait before that. The task stops when the
+    /// future returns `Ok(())` or when `policy` gives up.
+    ///
+    /// The number of restarts so far is passed to `policy`. Wrap `policy` into
+    /// [`Counted`] to read it outside of the task.
+    #[inline]
+    fn supervise<C, F, E, P>(self, mut factory: C, mut policy: P)
+    where
+        C: FnMut() -> F + Send + 'static,
+        F: Future<Output = Result<(), E>> + 'static,
+        E: 'static,
+        P: RestartPolicy<E>,
+    {
+        self.exec_factory(move || async move {
+            let mut restarts = 0_u32;
+            while let Err(err) = factory().await {
+                match policy.restart(err, restarts) {
+                    Some(delay) => delay.await,
+                    None => break,
+                }
+                restarts = restarts.saturating_add(1);
+            }
+        });
+    }
+
     /// Adds an executor for the future returned by `factory` to the fiber
     /// chain.
     ///
@@ -78,6 +147,59 @@ pub trait ThrExec: ThrToken {
     }
 }
 
+/// A restart policy for tasks supervised with [`ThrExec::supervise`].
+pub trait RestartPolicy<E>: Send + 'static {
+    /// The future returned by [`RestartPolicy::restart`].
+    type Delay: Future<Output = ()>;
+
+    /// Called when the supervised task returns the error `err`, where
+    /// `restarts` is the number of times the task has been restarted so far.
+    ///
+    /// Returns a future to wait for before restarting the task, or `None` to
+    /// stop the task.
+    fn restart(&mut self, err: E, restarts: u32) -> Option<Self::Delay>;
+}
+
+/// A restart policy, which restarts a failed task immediately at most the
+/// given number of times.
+#[derive(Clone, Copy, Debug)]
+pub struct MaxRestarts(pub u32);
+
+/// A restart policy, which delegates to the policy `P`, and stores the number
+/// of restarts of the supervised task into a [`RestartCounter`].
+///
+/// # Examples
+///
+/// ```
+/// use drone_core::thr::prelude::*;
+/// use drone_core::thr::{Counted, MaxRestarts, RestartCounter};
+/// use drone_core::token::Token;
+/// use drone_core::thr;
+///
+/// thr::soft! {
+///     thread => Thr {};
+///     local => ThrLocal {};
+///     index => Thrs;
+///     threads => { worker; };
+/// }
+///
+/// static RESTARTS: RestartCounter = RestartCounter::new();
+///
+/// let thr = unsafe { Thrs::take() };
+/// thr.worker.supervise(|| async { Err(()) }, Counted::new(MaxRestarts(3), &RESTARTS));
+/// assert_eq!(RESTARTS.get(), 3);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Counted<P> {
+    policy: P,
+    counter: &'static RestartCounter,
+}
+
+/// A counter of restarts of a supervised task.
+///
+/// See [`Counted`].
+pub struct RestartCounter(CounterState);
+
 /// A trait for implementing arbitrary output types for futures passed to
 /// [`ThrExec::exec`] and [`ThrExec::add_exec`].
 pub trait ExecOutput: Sized + Send {
@@ -88,6 +210,9 @@ pub trait ExecOutput: Sized + Send {
     /// A result handler for an executor. The returned value will not be used,
     /// so the only useful types are `()` and `!`. The handler may choose to
     /// panic on an erroneous value.
+    ///
+    /// The implementations for `Result` panic on an error. Use
+    /// [`ThrExec::exec_with_handler`] to handle errors without panicking.
     fn terminate(self) -> Self::Terminate;
 }
 
@@ -131,6 +256,65 @@ impl<E: Send + Display> ExecOutput for Result<!, E> {
     }
 }
 
+impl<E> RestartPolicy<E> for MaxRestarts {
+    type Delay = Ready<()>;
+
+    #[inline]
+    fn restart(&mut self, _err: E, restarts: u32) -> Option<Ready<()>> {
+        (restarts < self.0).then(|| future::ready(()))
+    }
+}
+
+impl<P> Counted<P> {
+    /// Creates a new policy, which delegates to `policy` and stores the
+    /// number of restarts into `counter`.
+    #[inline]
+    pub fn new(policy: P, counter: &'static RestartCounter) -> Self {
+        Self { policy, counter }
+    }
+}
+
+impl<E, P: RestartPolicy<E>> RestartPolicy<E> for Counted<P> {
+    type Delay = P::Delay;
+
+    #[inline]
+    fn restart(&mut self, err: E, restarts: u32) -> Option<P::Delay> {
+        let delay = self.policy.restart(err, restarts);
+        if delay.is_some() {
+            store_atomic!(self.counter.0, restarts.saturating_add(1), Relaxed);
+        }
+        delay
+    }
+}
+
+impl RestartCounter {
+    /// Creates a new counter with zero restarts.
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Self(CounterState::new(0))
+    }
+
+    /// Returns the number of restarts of the supervised task so far.
+    #[inline]
+    pub fn get(&self) -> u32 {
+        load_atomic!(self.0, Relaxed)
+    }
+}
+
+impl Default for RestartCounter {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RestartCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RestartCounter").field(&self.get()).finish()
+    }
+}
+
 fn terminate_err<E: Display>(err: E) -> ! {
     panic!("root future error: {}", err);
 }
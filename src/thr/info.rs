@@ -0,0 +1,60 @@
+use super::Thread;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+/// Run-time metadata of a thread in a thread pool.
+///
+/// See [`Thread::iter`].
+#[derive(Clone, Copy, Debug)]
+pub struct ThreadInfo {
+    /// Position of the thread within the thread pool.
+    pub index: u16,
+    /// Thread identifier given to `thr::pool!` macro.
+    pub name: &'static str,
+    /// Doc comments given to the thread in `thr::pool!` macro.
+    pub doc: &'static str,
+    /// `true` if the fiber chain of the thread is empty.
+    pub is_empty: bool,
+}
+
+/// An iterator over all threads in the thread pool `T`.
+///
+/// Created by [`Thread::iter`].
+pub struct ThreadIter<T: Thread> {
+    index: u16,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Thread> ThreadIter<T> {
+    pub(super) fn new() -> Self {
+        Self { index: 0, _marker: PhantomData }
+    }
+}
+
+impl<T: Thread> Iterator for ThreadIter<T> {
+    type Item = ThreadInfo;
+
+    fn next(&mut self) -> Option<ThreadInfo> {
+        let index = self.index;
+        if index >= T::COUNT {
+            return None;
+        }
+        self.index += 1;
+        let thr = unsafe { &*T::pool().add(usize::from(index)) };
+        Some(ThreadInfo {
+            index,
+            name: T::name(index),
+            doc: T::doc(index),
+            is_empty: thr.fib_chain().is_empty(),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::from(T::COUNT - self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Thread> ExactSizeIterator for ThreadIter<T> {}
+
+impl<T: Thread> FusedIterator for ThreadIter<T> {}
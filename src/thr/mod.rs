@@ -50,14 +50,20 @@
 //! ```
 
 pub mod prelude;
+#[cfg(feature = "host")]
+pub mod sim;
 
 mod exec;
+mod info;
 mod soft;
+mod task_local;
 
-pub use self::exec::{ExecOutput, ThrExec};
+pub use self::exec::{Counted, ExecOutput, MaxRestarts, RestartCounter, RestartPolicy, ThrExec};
+pub use self::info::{ThreadInfo, ThreadIter};
 pub use self::soft::{
     pending_size, PendingState, PriorityState, SoftThrToken, SoftThread, PRIORITY_LEVELS,
 };
+pub use self::task_local::{LocalKey, TaskLocalFuture};
 use crate::fib::{Chain, RootFiber};
 use crate::token::Token;
 /// Defines a thread pool.
@@ -101,6 +107,34 @@ pub unsafe trait Thread: Sized + Sync + 'static {
     /// Returns a raw pointer to the current thread index storage.
     fn current() -> *const CurrentState;
 
+    /// Returns the name of the thread number `thr_idx`, which is the thread
+    /// identifier given to `thr::pool!` macro.
+    ///
+    /// The default implementation returns an empty string.
+    ///
+    /// # Panics
+    ///
+    /// If `thr_idx` is more than or equal to [`Thread::COUNT`].
+    #[inline]
+    fn name(thr_idx: u16) -> &'static str {
+        assert!(thr_idx < Self::COUNT);
+        ""
+    }
+
+    /// Returns the documentation of the thread number `thr_idx`, which is
+    /// collected from doc comments given to `thr::pool!` macro.
+    ///
+    /// The default implementation returns an empty string.
+    ///
+    /// # Panics
+    ///
+    /// If `thr_idx` is more than or equal to [`Thread::COUNT`].
+    #[inline]
+    fn doc(thr_idx: u16) -> &'static str {
+        assert!(thr_idx < Self::COUNT);
+        ""
+    }
+
     /// Returns a reference to the fiber chain.
     fn fib_chain(&self) -> &Chain;
 
@@ -138,6 +172,41 @@ pub unsafe trait Thread: Sized + Sync + 'static {
         }
     }
 
+    /// Returns the index of the currently executing thread of this pool.
+    ///
+    /// If called outside of this thread pool, returns `None`.
+    #[inline]
+    fn current_index() -> Option<u16> {
+        unsafe { load_atomic!(*Self::current(), Relaxed) }.checked_sub(1)
+    }
+
+    /// Returns an iterator over all threads in this pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # drone_core::thr::pool! {
+    /// #     thread => Thr {};
+    /// #     local => ThrLocal {};
+    /// #     index => Thrs;
+    /// #     threads => {
+    /// #         /// The system tick handler.
+    /// #         sys_tick;
+    /// #     };
+    /// # }
+    /// # fn main() {
+    /// use drone_core::thr::Thread;
+    ///
+    /// for thread in Thr::iter() {
+    ///     println!("#{} {}: {}", thread.index, thread.name, thread.doc);
+    /// }
+    /// # }
+    /// ```
+    #[inline]
+    fn iter() -> ThreadIter<Self> {
+        ThreadIter::new()
+    }
+
     /// Resumes each fiber attached to the thread.
     ///
     /// # Safety
@@ -159,7 +228,7 @@ pub unsafe trait Thread: Sized + Sync + 'static {
         unsafe {
             let preempted = load_atomic!(*Self::current(), Relaxed);
             store_atomic!(*Self::current(), thr_idx + 1, Relaxed);
-            f(&*Self::pool().add(usize::from(thr_idx)));
+            task_local::isolate(|| f(&*Self::pool().add(usize::from(thr_idx))));
             store_atomic!(*Self::current(), preempted, Relaxed);
         }
     }
@@ -0,0 +1,238 @@
+//! Host-side scheduler simulation for software-managed threads.
+//!
+//! Under the `host` feature, a [`SoftThread`] pool can be driven by a
+//! [`Sim`], which injects a sequence of interrupt [`Event`]s and records the
+//! resulting execution [`Trace`]. The pool must be defined with this module's
+//! [`resume`] function as its `resume` hook. Each time a fiber of a running
+//! thread yields, the next event is injected before the thread resumes its
+//! next fiber, so that higher-priority threads set pending by the event preempt
+//! it the same way they would on the target. When no thread is running, the
+//! remaining events are injected one by one, and the threads left pending are
+//! run after each event.
+//!
+//! The sequence of events is either scripted with [`Sim::new`], or generated
+//! from a seed with [`Sim::seeded`], which gives the same sequence for the same
+//! seed.
+//!
+//! # Examples
+//!
+//! ```
+//! use drone_core::thr::prelude::*;
+//! use drone_core::thr::sim::{Event, Sim};
+//! use drone_core::token::Token;
+//! use drone_core::{fib, thr};
+//!
+//! thr::soft! {
+//!     thread => Thr {};
+//!     local => ThrLocal {};
+//!     index => Thrs;
+//!     threads => { low; high; };
+//!     resume => thr::sim::resume;
+//! }
+//!
+//! let thr = unsafe { Thrs::take() };
+//! thr.low.set_priority(0);
+//! thr.high.set_priority(1);
+//! thr.low.add_fn(|| fib::Yielded::<(), ()>(()));
+//! thr.high.add_fn(|| fib::Yielded::<(), ()>(()));
+//!
+//! let trace = Sim::<Thr>::new([Event::Pend(0), Event::Pend(1), Event::Pend(0)]).run();
+//! assert_eq!(trace.runs(), [0, 1, 0]);
+//! assert_eq!(trace.preemptions(), [(0, 1)]);
+//! ```
+
+use crate::thr::{SoftThread, Thread};
+use core::cell::RefCell;
+use core::marker::PhantomData;
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+std::thread_local! {
+    static STATE: RefCell<Option<State>> = RefCell::new(None);
+}
+
+/// An interrupt event injected by [`Sim`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Sets the thread pending, as [`SoftThread::set_pending`] does.
+    Pend(u16),
+    /// Sets the thread pending at the priority level, as
+    /// [`SoftThread::set_pending_at`] does.
+    PendAt(u16, u8),
+}
+
+/// A step of the execution trace recorded by [`Sim`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// The event was injected.
+    Event(Event),
+    /// The thread started running.
+    Enter(u16),
+    /// The thread finished running.
+    Exit(u16),
+}
+
+/// An execution trace recorded by [`Sim`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace(Vec<Step>);
+
+/// A deterministic scheduler simulator for the `T` thread pool.
+pub struct Sim<T: SoftThread> {
+    events: VecDeque<Event>,
+    _marker: PhantomData<T>,
+}
+
+struct State {
+    events: VecDeque<Event>,
+    steps: Vec<Step>,
+}
+
+/// Clears the simulation state even if a thread panics.
+struct StateGuard;
+
+impl<T: SoftThread> Sim<T> {
+    /// Creates a new simulator, which injects `events` in order.
+    pub fn new<I: IntoIterator<Item = Event>>(events: I) -> Self {
+        Self { events: events.into_iter().collect(), _marker: PhantomData }
+    }
+
+    /// Creates a new simulator, which injects `count` events setting random
+    /// threads pending.
+    ///
+    /// The sequence of events is determined by `seed` only.
+    ///
+    /// # Panics
+    ///
+    /// If `count` is not zero and the thread pool is empty.
+    pub fn seeded(seed: u32, count: usize) -> Self {
+        let mut state = if seed == 0 { 0x9E37_79B9 } else { seed };
+        Self::new((0..count).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            Event::Pend((state % u32::from(T::COUNT)) as u16)
+        }))
+    }
+
+    /// Returns the events to be injected.
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    /// Injects all events, and returns the recorded trace.
+    ///
+    /// # Panics
+    ///
+    /// * If another simulation is running on the current OS thread.
+    /// * If an event refers to a non-existent thread or priority level.
+    pub fn run(self) -> Trace {
+        STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            assert!(state.is_none(), "another simulation is running");
+            *state = Some(State { events: self.events, steps: Vec::new() });
+        });
+        let _guard = StateGuard;
+        while inject::<T>() {
+            T::preempt();
+        }
+        let state = STATE.with(|state| state.borrow_mut().take());
+        Trace(state.map(|state| state.steps).unwrap_or_default())
+    }
+}
+
+impl Trace {
+    /// Returns the recorded steps.
+    pub fn steps(&self) -> &[Step] {
+        &self.0
+    }
+
+    /// Returns the indices of the threads in the order they started running.
+    pub fn runs(&self) -> Vec<u16> {
+        self.0
+            .iter()
+            .filter_map(|step| if let Step::Enter(thr_idx) = *step { Some(thr_idx) } else { None })
+            .collect()
+    }
+
+    /// Returns pairs of thread indices `(preempted, preempting)` in the order
+    /// the preemptions happened.
+    pub fn preemptions(&self) -> Vec<(u16, u16)> {
+        let mut running = Vec::new();
+        let mut preemptions = Vec::new();
+        for step in &self.0 {
+            match *step {
+                Step::Enter(thr_idx) => {
+                    if let Some(&preempted) = running.last() {
+                        preemptions.push((preempted, thr_idx));
+                    }
+                    running.push(thr_idx);
+                }
+                Step::Exit(_) => {
+                    running.pop();
+                }
+                Step::Event(_) => {}
+            }
+        }
+        preemptions
+    }
+}
+
+/// Resumes the thread, and injects the next event of the running simulation
+/// after each of its fibers.
+///
+/// This function is meant to be passed as the `resume` hook to
+/// [`thr::soft!`](crate::thr::soft). Outside of [`Sim::run`], it only resumes
+/// the thread.
+///
+/// # Safety
+///
+/// See [`Thread::resume`].
+pub unsafe fn resume<T: SoftThread>(thr: &T) {
+    let thr_idx = unsafe { (thr as *const T).offset_from(T::pool()) } as u16;
+    record(Step::Enter(thr_idx));
+    unsafe {
+        thr.fib_chain().drain_inspect(|| {
+            inject::<T>();
+        })
+    }
+    .for_each(drop);
+    record(Step::Exit(thr_idx));
+}
+
+impl Drop for StateGuard {
+    fn drop(&mut self) {
+        STATE.with(|state| state.borrow_mut().take());
+    }
+}
+
+fn inject<T: SoftThread>() -> bool {
+    let event = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let state = state.as_mut()?;
+        let event = state.events.pop_front()?;
+        state.steps.push(Step::Event(event));
+        Some(event)
+    });
+    match event {
+        Some(Event::Pend(thr_idx)) => {
+            assert!(thr_idx < T::COUNT, "thread index out of range");
+            unsafe { T::set_pending(thr_idx) };
+            true
+        }
+        Some(Event::PendAt(thr_idx, priority)) => {
+            assert!(thr_idx < T::COUNT, "thread index out of range");
+            assert!(priority < T::PRIORITY_LEVELS, "priority out of range");
+            unsafe { T::set_pending_at(thr_idx, priority) };
+            true
+        }
+        None => false,
+    }
+}
+
+fn record(step: Step) {
+    STATE.with(|state| {
+        if let Some(state) = state.borrow_mut().as_mut() {
+            state.steps.push(step);
+        }
+    });
+}
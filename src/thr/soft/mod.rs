@@ -1,23 +1,58 @@
 mod wake;
 
 use self::wake::SoftWaker;
+use crate::platform::Interrupts;
 use crate::thr::{ThrExec, ThrToken, Thread};
 use core::task::Waker;
 
-/// Number of priority levels.
+/// Default number of priority levels, which is also the maximum number of
+/// priority levels for the single-word header layout.
+///
+/// See [`SoftThread::PRIORITY_LEVELS`].
 pub const PRIORITY_LEVELS: u8 = 27;
 
 /// Returns the number of elements in [`SoftThread::pending`] array.
 pub const fn pending_size<T: SoftThread>() -> usize {
-    1 + row_size::<T>() * PRIORITY_LEVELS as usize
+    let size = header_size::<T>() + row_size::<T>() * T::PRIORITY_LEVELS as usize;
+    let size = if T::ROUND_ROBIN { size + T::PRIORITY_LEVELS as usize } else { size };
+    size + row_size::<T>() * 2 + 1
+}
+
+const fn is_wide<T: SoftThread>() -> bool {
+    T::PRIORITY_LEVELS > PRIORITY_LEVELS
+}
+
+const fn header_size<T: SoftThread>() -> usize {
+    if is_wide::<T>() { 1 + (T::PRIORITY_LEVELS as usize + 31 >> 5) } else { 1 }
 }
 
 const fn row_size<T: SoftThread>() -> usize {
     (T::COUNT >> COL_BITS) as usize + (T::COUNT & (1 << COL_BITS) - 1 > 0) as usize
 }
 
+const fn row_idx<T: SoftThread>(priority: u8) -> usize {
+    header_size::<T>() + row_size::<T>() * (T::PRIORITY_LEVELS - priority) as usize
+}
+
+const fn rotation_idx<T: SoftThread>(priority: u8) -> usize {
+    row_idx::<T>(0) + (priority - 1) as usize
+}
+
+const fn running_idx<T: SoftThread>(thr_idx: u16) -> usize {
+    let idx = row_idx::<T>(0) + (thr_idx >> COL_BITS) as usize;
+    if T::ROUND_ROBIN { idx + T::PRIORITY_LEVELS as usize } else { idx }
+}
+
+const fn deferred_idx<T: SoftThread>(thr_idx: u16) -> usize {
+    running_idx::<T>(thr_idx) + row_size::<T>()
+}
+
+const fn idle_idx<T: SoftThread>() -> usize {
+    pending_size::<T>() - 1
+}
+
 const fn cell_idx<T: SoftThread>(thr_idx: u16, priority: u8) -> usize {
-    1 + row_size::<T>() * (PRIORITY_LEVELS - 1 - priority) as usize + (thr_idx >> COL_BITS) as usize
+    row_idx::<T>(priority + 1) + (thr_idx >> COL_BITS) as usize
 }
 
 const fn pending_bit(thr_idx: u16) -> u32 {
@@ -26,6 +61,9 @@ const fn pending_bit(thr_idx: u16) -> u32 {
 
 const COL_BITS: u32 = 5;
 
+const WIDE_CURSOR_SHIFT: u32 = 24;
+const WIDE_GROUP_MASK: u32 = (1 << WIDE_CURSOR_SHIFT) - 1;
+
 #[cfg(all(feature = "atomics", not(loom)))]
 #[doc(hidden)]
 pub type PendingState = core::sync::atomic::AtomicU32;
@@ -60,7 +98,8 @@ pub type PriorityState = crate::sync::soft_atomic::Atomic<u8>;
 /// * `C<n>` - a set of pending status bits for each defined thread at the
 ///   priority level `n`
 ///
-/// The header has the following bit structure:
+/// If [`SoftThread::PRIORITY_LEVELS`] is not more than [`PRIORITY_LEVELS`],
+/// the header is a single word with the following bit structure:
 ///
 /// `CCCCCPPP PPPPPPPP ...`, where
 ///
@@ -68,17 +107,79 @@ pub type PriorityState = crate::sync::soft_atomic::Atomic<u8>;
 ///   0 means no thread of this thread pool is currently running
 /// * `P` - a set of pending status bits for each priority level
 ///
+/// The single-word header is updated with lock-free atomic operations.
+///
+/// Otherwise the header is a two-level bitmap `[H0, G<0>, G<1>, ...]`, where
+/// `G<n>` is a set of pending status bits for the priority levels from `32 * n`
+/// to `32 * n + 31`, and `H0` has the following bit structure:
+///
+/// `CCCCCCCC 00000000 00000000 GGGGGGGG`, where
+///
+/// * `C` bits form a number of the currently running priority plus 1
+/// * `G` - a set of non-empty status bits for each `G<n>` word
+///
+/// The two-level header is updated inside critical sections, and allows up to
+/// 255 priority levels. Finding the highest pending priority level takes a
+/// constant time in both layouts.
+///
+/// If [`SoftThread::ROUND_ROBIN`] is `true`, the array is followed by
+/// [`SoftThread::PRIORITY_LEVELS`] words, each holding the thread index to
+/// start the next run of the corresponding priority level from.
+///
+/// Then follow two sets of status bits for each defined thread: `R` - the
+/// thread is running, and `D` - the thread is set pending while running. A
+/// thread set pending at a priority level higher than the level it is running
+/// at is not resumed again on top of itself. Instead, the run is deferred until
+/// the running thread returns.
+///
+/// The last word of the array counts the entries to the idle state. See
+/// [`SoftThread::idle_count`].
+///
 /// # Safety
 ///
 /// [`SoftThread::pending`] must point to a static array with [`pending_size`]
 /// number of elements.
 pub unsafe trait SoftThread: Thread {
+    /// Number of priority levels.
+    const PRIORITY_LEVELS: u8 = PRIORITY_LEVELS;
+
+    /// Whether pending threads of the same priority are run in the round-robin
+    /// order.
+    ///
+    /// By default, pending threads of the same priority are always run in the
+    /// order of their indices. In the round-robin mode, each run of a priority
+    /// level starts from the thread following the first thread served by the
+    /// previous run, so that no thread is always served first.
+    const ROUND_ROBIN: bool = false;
+
     /// Returns a raw pointer to the pending state storage.
     fn pending() -> *const PendingState;
 
     /// Returns a raw pointer to the thread priority storage.
     fn priority(&self) -> *const PriorityState;
 
+    /// Called by [`SoftThread::run_idle`] when no thread of this thread pool
+    /// is running or pending.
+    ///
+    /// This function is called inside a critical section, right after the
+    /// pending state is checked, so an interrupt setting a thread pending
+    /// cannot slip in between. It is the place to enter a low-power mode with
+    /// an instruction that wakes up on a pending interrupt, like `WFI`. The
+    /// interrupt is handled after this function returns.
+    ///
+    /// This function must not set threads of this thread pool pending.
+    #[inline]
+    fn idle() {}
+
+    /// Returns the number of times this thread pool has entered the idle
+    /// state, wrapping around on overflow.
+    ///
+    /// See [`SoftThread::idle`].
+    #[inline]
+    fn idle_count() -> u32 {
+        unsafe { load_atomic!(*Self::pending().add(idle_idx::<Self>()), Relaxed) }
+    }
+
     /// Sets the `thr_idx` thread pending.
     ///
     /// See [the trait level documentation](SoftThread) for details.
@@ -107,7 +208,45 @@ pub unsafe trait SoftThread: Thread {
         unsafe {
             let thr = Self::pool().add(usize::from(thr_idx));
             let priority = load_atomic!(*(*thr).priority(), Relaxed);
-            set_pending(
+            Self::will_preempt_at(thr_idx, priority)
+        }
+    }
+
+    /// Sets the `thr_idx` thread pending at the `priority` level for one run.
+    ///
+    /// The thread priority is not changed, and the pending state is cleared
+    /// when the thread is run at the `priority` level. If `priority` is lower
+    /// than the thread priority, the thread priority is used instead. If the
+    /// thread is already running, it is run again right after it returns.
+    ///
+    /// # Safety
+    ///
+    /// * `thr_idx` must be less than [`Thread::COUNT`].
+    /// * `priority` must be less than [`SoftThread::PRIORITY_LEVELS`].
+    /// * This function doesn't check for the thread token ownership.
+    unsafe fn set_pending_at(thr_idx: u16, priority: u8) {
+        if unsafe { Self::will_preempt_at(thr_idx, priority) } {
+            Self::preempt();
+        }
+    }
+
+    /// Sets the `thr_idx` thread pending at the `priority` level for one run,
+    /// and returns `true` if `priority` is higher than the current priority.
+    ///
+    /// If this function returned `true`, a subsequent call to
+    /// [`SoftThread::preempt`] is needed. See also
+    /// [`SoftThread::set_pending_at`].
+    ///
+    /// # Safety
+    ///
+    /// * `thr_idx` must be less than [`Thread::COUNT`].
+    /// * `priority` must be less than [`SoftThread::PRIORITY_LEVELS`].
+    /// * This function doesn't check for the thread token ownership.
+    unsafe fn will_preempt_at(thr_idx: u16, priority: u8) -> bool {
+        unsafe {
+            let thr = Self::pool().add(usize::from(thr_idx));
+            let priority = priority.max(load_atomic!(*(*thr).priority(), Relaxed));
+            set_pending::<Self>(
                 Self::pending(),
                 cell_idx::<Self>(thr_idx, priority),
                 pending_bit(thr_idx),
@@ -119,22 +258,41 @@ pub unsafe trait SoftThread: Thread {
     /// Runs all pending threads with higher priorities than the current
     /// priority.
     fn preempt() {
-        unsafe fn resume<T: Thread>(thr_idx: u16) {
-            unsafe { T::call(thr_idx, T::resume) };
+        unsafe fn resume<T: SoftThread>(thr_idx: u16) {
+            unsafe { run::<T>(T::pending(), thr_idx) };
         }
         let pending = Self::pending();
-        let row_size = row_size::<Self>();
         unsafe {
-            if let Some((mut ptr, mut priority, prev_priority)) = row_start(pending, row_size) {
+            if let Some((mut priority, prev_priority)) = row_start::<Self>(pending) {
                 loop {
-                    row_run(&mut ptr, Self::COUNT, resume::<Self>);
-                    if !row_next(pending, &mut ptr, &mut priority, prev_priority, row_size) {
+                    let mut ptr = pending.add(row_idx::<Self>(priority));
+                    if Self::ROUND_ROBIN {
+                        let rotation = pending.add(rotation_idx::<Self>(priority));
+                        let start = load_atomic!(*rotation, Relaxed) as u16;
+                        let start = row_run_from(ptr, Self::COUNT, start, resume::<Self>);
+                        store_atomic!(*rotation, u32::from(start), Relaxed);
+                    } else {
+                        row_run(&mut ptr, Self::COUNT, resume::<Self>);
+                    }
+                    if !row_next::<Self>(pending, &mut priority, prev_priority) {
                         break;
                     }
                 }
             }
         }
     }
+
+    /// Runs all pending threads, and then calls [`SoftThread::idle`] if no
+    /// thread of this thread pool is left running or pending.
+    ///
+    /// This is the idle entry point of the thread pool. It is meant to be
+    /// called in a loop from the context, which owns the CPU when no thread
+    /// is running, e.g. the main loop of the reset handler. Calling it from a
+    /// thread of this thread pool only runs the pending threads.
+    fn run_idle() {
+        Self::preempt();
+        unsafe { enter_idle::<Self>(Self::pending()) };
+    }
 }
 
 /// Token for a software-managed thread.
@@ -154,6 +312,39 @@ pub trait SoftThrToken: ThrToken {
         unsafe { Self::SoftThread::set_pending(Self::THR_IDX) };
     }
 
+    /// Sets the thread pending at the `priority` level for one run, without
+    /// changing the thread priority.
+    ///
+    /// This allows an urgent event to be serviced promptly by a normally
+    /// low-priority thread. If `priority` is lower than the thread priority,
+    /// the thread priority is used instead. If the thread is already running,
+    /// it is run again right after it returns. The boosted pending state is not
+    /// observed by [`SoftThrToken::is_pending`] and
+    /// [`SoftThrToken::clear_pending`].
+    ///
+    /// # Panics
+    ///
+    /// If `priority` is greater than or equals to
+    /// [`SoftThread::PRIORITY_LEVELS`].
+    #[inline]
+    fn wakeup_at(self, priority: u8) {
+        assert!(priority < Self::SoftThread::PRIORITY_LEVELS);
+        SoftWaker::<Self::SoftThread>::with_priority(Self::THR_IDX, priority).wakeup();
+    }
+
+    /// Returns a handle for waking up the thread at the `priority` level. See
+    /// [`SoftThrToken::wakeup_at`].
+    ///
+    /// # Panics
+    ///
+    /// If `priority` is greater than or equals to
+    /// [`SoftThread::PRIORITY_LEVELS`].
+    #[inline]
+    fn waker_at(self, priority: u8) -> Waker {
+        assert!(priority < Self::SoftThread::PRIORITY_LEVELS);
+        SoftWaker::<Self::SoftThread>::with_priority(Self::THR_IDX, priority).to_waker()
+    }
+
     /// Clears the thread pending state.
     #[inline]
     fn clear_pending(self) {
@@ -188,10 +379,11 @@ pub trait SoftThrToken: ThrToken {
     ///
     /// # Panics
     ///
-    /// If `priority` is greater than or equals to [`PRIORITY_LEVELS`].
+    /// If `priority` is greater than or equals to
+    /// [`SoftThread::PRIORITY_LEVELS`].
     #[inline]
     fn set_priority(self, priority: u8) {
-        assert!(priority < PRIORITY_LEVELS);
+        assert!(priority < Self::SoftThread::PRIORITY_LEVELS);
         unsafe { store_atomic!(*self.to_soft_thr().priority(), priority, Relaxed) };
     }
 }
@@ -212,20 +404,22 @@ impl<T: SoftThrToken> ThrExec for T {
     }
 }
 
-unsafe fn row_start(
-    header: *const PendingState,
-    row_size: usize,
-) -> Option<(*const PendingState, u8, u8)> {
+unsafe fn row_start<T: SoftThread>(header: *const PendingState) -> Option<(u8, u8)> {
+    if is_wide::<T>() {
+        return Interrupts::paused(|| unsafe {
+            let prev_priority = (load_atomic!(*header, Acquire) >> WIDE_CURSOR_SHIFT) as u8;
+            wide_take(header, prev_priority).map(|priority| (priority, prev_priority))
+        });
+    }
     #[cfg_attr(any(feature = "atomics", loom), allow(unused_assignments))]
     let (mut priority, mut prev_priority) = (0, 0);
     load_try_modify_atomic!(unsafe { &*header }, Relaxed, Acquire, |cursor| {
-        priority = PRIORITY_LEVELS;
-        prev_priority = (cursor >> priority) as u8;
+        priority = T::PRIORITY_LEVELS;
+        prev_priority = (cursor >> PRIORITY_LEVELS) as u8;
         cursor_find_priority(cursor, &mut priority, prev_priority)
     })
     .ok()?;
-    let ptr = unsafe { header.add(1 + row_size * usize::from(PRIORITY_LEVELS - priority)) };
-    Some((ptr, priority, prev_priority))
+    Some((priority, prev_priority))
 }
 
 unsafe fn row_run(ptr: &mut *const PendingState, thr_count: u16, resume: unsafe fn(u16)) {
@@ -260,39 +454,170 @@ unsafe fn row_run(ptr: &mut *const PendingState, thr_count: u16, resume: unsafe
     }
 }
 
-unsafe fn row_next(
+unsafe fn row_run_from(
+    row: *const PendingState,
+    thr_count: u16,
+    start: u16,
+    resume: unsafe fn(u16),
+) -> u16 {
+    let first = unsafe { row_run_range(row, start, thr_count, resume) };
+    let rest = unsafe { row_run_range(row, 0, start, resume) };
+    first.or(rest).map_or(start, |thr_idx| (thr_idx + 1) % thr_count)
+}
+
+/// Runs pending threads with indices in `from..to`, and returns the index of
+/// the first one.
+unsafe fn row_run_range(
+    row: *const PendingState,
+    from: u16,
+    to: u16,
+    resume: unsafe fn(u16),
+) -> Option<u16> {
+    const COL_MASK: u32 = (1 << COL_BITS) - 1;
+    let mut first = None;
+    let mut thr_idx = u32::from(from);
+    while thr_idx < u32::from(to) {
+        let cell = unsafe { &*row.add((thr_idx >> COL_BITS) as usize) };
+        // Mask off the threads below `thr_idx`, which are already visited.
+        let pending = load_atomic!(cell, Relaxed) & !((1 << (thr_idx & COL_MASK)) - 1);
+        if pending == 0 {
+            thr_idx = (thr_idx | COL_MASK) + 1;
+            continue;
+        }
+        thr_idx = (thr_idx & !COL_MASK) + pending.trailing_zeros();
+        if thr_idx >= u32::from(to) {
+            break;
+        }
+        fetch_and_atomic!(cell, !(1 << (thr_idx & COL_MASK)), Acquire);
+        first.get_or_insert(thr_idx as u16);
+        unsafe { resume(thr_idx as u16) };
+        thr_idx += 1;
+    }
+    first
+}
+
+/// Resumes the `thr_idx` thread, unless it is already running lower in the
+/// stack. In the latter case the run is deferred, and performed by the running
+/// instance right after it returns.
+unsafe fn run<T: SoftThread>(pending: *const PendingState, thr_idx: u16) {
+    let pending_bit = pending_bit(thr_idx);
+    let running = unsafe { &*pending.add(running_idx::<T>(thr_idx)) };
+    let deferred = unsafe { &*pending.add(deferred_idx::<T>(thr_idx)) };
+    if fetch_or_atomic!(running, pending_bit, Acquire) & pending_bit != 0 {
+        fetch_or_atomic!(deferred, pending_bit, Release);
+        return;
+    }
+    loop {
+        unsafe { T::call(thr_idx, T::resume) };
+        // Checking the deferred bit and clearing the running bit must not be
+        // interleaved with a deferral from an interrupt.
+        let rerun = Interrupts::paused(|| {
+            if load_atomic!(deferred, Acquire) & pending_bit == 0 {
+                fetch_and_atomic!(running, !pending_bit, Release);
+                false
+            } else {
+                fetch_and_atomic!(deferred, !pending_bit, Relaxed);
+                true
+            }
+        });
+        if !rerun {
+            break;
+        }
+    }
+}
+
+unsafe fn row_next<T: SoftThread>(
     header: *const PendingState,
-    ptr: &mut *const PendingState,
     priority: &mut u8,
     prev_priority: u8,
-    row_size: usize,
 ) -> bool {
     #[cfg_attr(any(feature = "atomics", loom), allow(unused_assignments))]
     let mut next_priority = 0;
-    load_modify_atomic!(unsafe { &*header }, Relaxed, Release, |cursor| {
-        next_priority = *priority - 1;
-        cursor_find_priority(cursor, &mut next_priority, prev_priority)
-            .unwrap_or_else(|| cursor_set_priority(cursor, prev_priority))
-    });
+    if is_wide::<T>() {
+        next_priority = Interrupts::paused(|| unsafe {
+            wide_take(header, prev_priority).unwrap_or_else(|| {
+                let cursor = load_atomic!(*header, Relaxed) & WIDE_GROUP_MASK;
+                let cursor = cursor | u32::from(prev_priority) << WIDE_CURSOR_SHIFT;
+                store_atomic!(*header, cursor, Release);
+                prev_priority
+            })
+        });
+    } else {
+        load_modify_atomic!(unsafe { &*header }, Relaxed, Release, |cursor| {
+            next_priority = *priority - 1;
+            cursor_find_priority(cursor, &mut next_priority, prev_priority)
+                .unwrap_or_else(|| cursor_set_priority(cursor, prev_priority))
+        });
+    }
     if next_priority == prev_priority {
         return false;
     }
-    if next_priority != *priority {
-        *ptr = unsafe { ptr.add(row_size * usize::from(*priority - next_priority)) };
-        *priority = next_priority;
-    }
+    *priority = next_priority;
     true
 }
 
-unsafe fn set_pending(
+unsafe fn set_pending<T: SoftThread>(
     pending: *const PendingState,
     cell_idx: usize,
     pending_bit: u32,
     priority: u8,
 ) -> bool {
-    fetch_or_atomic!(unsafe { &*pending.add(cell_idx) }, pending_bit, Release) & pending_bit == 0
-        && fetch_or_atomic!(unsafe { &*pending }, 1 << priority, Release) >> PRIORITY_LEVELS
+    let cell = fetch_or_atomic!(unsafe { &*pending.add(cell_idx) }, pending_bit, Release);
+    if cell & pending_bit != 0 {
+        return false;
+    }
+    if is_wide::<T>() {
+        Interrupts::paused(|| unsafe {
+            let group = pending.add(1 + usize::from(priority >> 5));
+            let bits = load_atomic!(*group, Relaxed) | 1 << (priority & 31);
+            store_atomic!(*group, bits, Release);
+            let cursor = load_atomic!(*pending, Relaxed) | 1 << (priority >> 5);
+            store_atomic!(*pending, cursor, Release);
+            cursor >> WIDE_CURSOR_SHIFT < u32::from(priority + 1)
+        })
+    } else {
+        fetch_or_atomic!(unsafe { &*pending }, 1 << priority, Release) >> PRIORITY_LEVELS
             < u32::from(priority + 1)
+    }
+}
+
+/// Takes the highest pending priority level from the two-level header, if it
+/// is higher than `prev_priority`, and makes it the current priority. Must be
+/// called inside a critical section.
+unsafe fn wide_take(header: *const PendingState, prev_priority: u8) -> Option<u8> {
+    unsafe {
+        let cursor = load_atomic!(*header, Relaxed);
+        let groups = cursor & WIDE_GROUP_MASK;
+        if groups == 0 {
+            return None;
+        }
+        let group_idx = 31 - groups.leading_zeros();
+        let group = header.add(1 + group_idx as usize);
+        let bits = load_atomic!(*group, Relaxed);
+        let bit_idx = 31 - bits.leading_zeros();
+        let priority = (group_idx << 5 | bit_idx) as u8 + 1;
+        if priority <= prev_priority {
+            return None;
+        }
+        let bits = bits & !(1 << bit_idx);
+        store_atomic!(*group, bits, Relaxed);
+        let mut cursor = groups | u32::from(priority) << WIDE_CURSOR_SHIFT;
+        if bits == 0 {
+            cursor &= !(1 << group_idx);
+        }
+        store_atomic!(*header, cursor, Relaxed);
+        Some(priority)
+    }
+}
+
+unsafe fn enter_idle<T: SoftThread>(header: *const PendingState) {
+    Interrupts::paused(|| unsafe {
+        if load_atomic!(*header, Acquire) == 0 {
+            let counter = header.add(idle_idx::<T>());
+            store_atomic!(*counter, load_atomic!(*counter, Relaxed).wrapping_add(1), Relaxed);
+            T::idle();
+        }
+    });
 }
 
 unsafe fn clear_pending(pending: *const PendingState, cell_idx: usize, pending_bit: u32) {
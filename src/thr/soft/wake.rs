@@ -2,19 +2,28 @@ use super::SoftThread;
 use core::marker::PhantomData;
 use core::task::{RawWaker, RawWakerVTable, Waker};
 
-#[repr(transparent)]
+const PRIORITY_SHIFT: u32 = 16;
+
 pub struct SoftWaker<T: SoftThread> {
     thr_idx: u16,
+    boost: u8,
     _marker: PhantomData<T>,
 }
 
 impl<T: SoftThread> SoftWaker<T> {
     pub fn new(thr_idx: u16) -> Self {
-        Self { thr_idx, _marker: PhantomData }
+        Self { thr_idx, boost: 0, _marker: PhantomData }
+    }
+
+    pub fn with_priority(thr_idx: u16, priority: u8) -> Self {
+        Self { thr_idx, boost: priority + 1, _marker: PhantomData }
     }
 
     pub fn wakeup(&self) {
-        unsafe { T::set_pending(self.thr_idx) };
+        match self.boost.checked_sub(1) {
+            Some(priority) => unsafe { T::set_pending_at(self.thr_idx, priority) },
+            None => unsafe { T::set_pending(self.thr_idx) },
+        }
     }
 
     pub fn to_waker(&self) -> Waker {
@@ -22,17 +31,23 @@ impl<T: SoftThread> SoftWaker<T> {
     }
 
     fn to_raw_waker(&self) -> RawWaker {
+        let data = usize::from(self.thr_idx) | usize::from(self.boost) << PRIORITY_SHIFT;
         RawWaker::new(
-            self.thr_idx as *const (),
+            data as *const (),
             &RawWakerVTable::new(Self::clone, Self::wake, Self::wake, drop),
         )
     }
 
+    fn from_data(data: *const ()) -> Self {
+        let data = data as usize;
+        Self { thr_idx: data as u16, boost: (data >> PRIORITY_SHIFT) as u8, _marker: PhantomData }
+    }
+
     unsafe fn clone(data: *const ()) -> RawWaker {
-        Self::new(data as u16).to_raw_waker()
+        Self::from_data(data).to_raw_waker()
     }
 
     unsafe fn wake(data: *const ()) {
-        Self::new(data as u16).wakeup();
+        Self::from_data(data).wakeup();
     }
 }
@@ -0,0 +1,201 @@
+use core::future::Future;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+use core::ptr;
+use core::task::{Context, Poll};
+
+#[cfg(not(feature = "host"))]
+static CURRENT: core::sync::atomic::AtomicPtr<Frame> =
+    core::sync::atomic::AtomicPtr::new(ptr::null_mut());
+
+#[cfg(feature = "host")]
+std::thread_local! {
+    static CURRENT: core::cell::Cell<*const Frame> = core::cell::Cell::new(ptr::null());
+}
+
+/// Declares task-local keys of type [`LocalKey`].
+///
+/// A task-local value is set for the duration of a future with
+/// [`LocalKey::scope`], and is accessed with [`LocalKey::with`] from inside
+/// that future. Unlike [`Thread::local`](crate::thr::Thread::local), the value
+/// follows the future rather than the thread: futures executed on the same
+/// thread with [`ThrExec::exec`](crate::thr::ThrExec::exec) don't see each
+/// other's values, and a thread preempting the future doesn't see its values
+/// either.
+///
+/// # Examples
+///
+/// ```
+/// use drone_core::thr::prelude::*;
+/// use drone_core::token::Token;
+/// use drone_core::{task_local, thr};
+///
+/// thr::soft! {
+///     thread => Thr {};
+///     local => ThrLocal {};
+///     index => Thrs;
+///     threads => { worker; };
+/// }
+///
+/// task_local! {
+///     /// Identifier of the request being served.
+///     pub static REQUEST_ID: u32;
+/// }
+///
+/// let thr = unsafe { Thrs::take() };
+/// thr.worker.exec(REQUEST_ID.scope(42, async {
+///     assert_eq!(REQUEST_ID.with(|id| *id), 42);
+///     REQUEST_ID.scope(43, async { assert_eq!(REQUEST_ID.with(|id| *id), 43) }).await;
+///     assert_eq!(REQUEST_ID.with(|id| *id), 42);
+/// }));
+/// assert_eq!(REQUEST_ID.try_with(|id| *id), None);
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty; $($rest:tt)*) => {
+        $(#[$attr])*
+        $vis static $name: $crate::thr::LocalKey<$ty> = $crate::thr::LocalKey::new();
+        $crate::task_local!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty) => {
+        $crate::task_local!($(#[$attr])* $vis static $name: $ty;);
+    };
+}
+
+/// A key for task-local values.
+///
+/// This type is declared with [`task_local!`](crate::task_local) macro.
+pub struct LocalKey<T: 'static> {
+    _marker: PhantomData<fn() -> T>,
+    // Makes each key occupy a distinct address.
+    _unique: u8,
+}
+
+/// A future that sets a task-local value for the duration of the inner future.
+///
+/// This future is returned by [`LocalKey::scope`]. The value is also set while
+/// the inner future is dropped.
+pub struct TaskLocalFuture<T: 'static, F> {
+    key: &'static LocalKey<T>,
+    value: T,
+    fut: ManuallyDrop<F>,
+}
+
+struct Frame {
+    key: *const (),
+    value: *const (),
+    prev: *const Frame,
+}
+
+struct Restore(*const Frame);
+
+impl<T: 'static> LocalKey<T> {
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self { _marker: PhantomData, _unique: 0 }
+    }
+
+    /// Sets the task-local value to `value` for the duration of the future
+    /// `fut`.
+    ///
+    /// The value is set only while `fut` is being polled, and shadows the
+    /// value set by an outer scope of the same key.
+    pub fn scope<F: Future>(&'static self, value: T, fut: F) -> TaskLocalFuture<T, F> {
+        TaskLocalFuture { key: self, value, fut: ManuallyDrop::new(fut) }
+    }
+
+    /// Calls `f` with a reference to the task-local value.
+    ///
+    /// # Panics
+    ///
+    /// If the value is not set by an enclosing [`LocalKey::scope`].
+    pub fn with<F: FnOnce(&T) -> R, R>(&'static self, f: F) -> R {
+        self.try_with(f).expect("task-local value is not set")
+    }
+
+    /// Calls `f` with a reference to the task-local value if it is set.
+    /// Returns `None` otherwise.
+    pub fn try_with<F: FnOnce(&T) -> R, R>(&'static self, f: F) -> Option<R> {
+        let key = (self as *const Self).cast();
+        let mut frame = load_current();
+        while let Some(Frame { key: frame_key, value, prev }) = unsafe { frame.as_ref() } {
+            if *frame_key == key {
+                return Some(f(unsafe { &*value.cast::<T>() }));
+            }
+            frame = *prev;
+        }
+        None
+    }
+}
+
+unsafe impl<T: 'static> Sync for LocalKey<T> {}
+
+impl<T: 'static, F: Future> Future for TaskLocalFuture<T, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let frame = this.frame();
+        let _restore = enter(&frame);
+        unsafe { Pin::new_unchecked(&mut *this.fut) }.poll(cx)
+    }
+}
+
+impl<T: 'static, F> TaskLocalFuture<T, F> {
+    fn frame(&self) -> Frame {
+        Frame {
+            key: (self.key as *const LocalKey<T>).cast(),
+            value: (&self.value as *const T).cast(),
+            prev: load_current(),
+        }
+    }
+}
+
+impl<T: 'static, F> Drop for TaskLocalFuture<T, F> {
+    fn drop(&mut self) {
+        let frame = self.frame();
+        let _restore = enter(&frame);
+        unsafe { ManuallyDrop::drop(&mut self.fut) };
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        store_current(self.0);
+    }
+}
+
+/// Runs `f` as a separate task, which doesn't see the task-local values of the
+/// current task.
+pub(crate) fn isolate<R, F: FnOnce() -> R>(f: F) -> R {
+    let _restore = enter(ptr::null());
+    f()
+}
+
+fn enter(frame: *const Frame) -> Restore {
+    let prev = load_current();
+    store_current(frame);
+    Restore(prev)
+}
+
+#[cfg(not(feature = "host"))]
+fn load_current() -> *const Frame {
+    CURRENT.load(core::sync::atomic::Ordering::Relaxed)
+}
+
+#[cfg(not(feature = "host"))]
+fn store_current(frame: *const Frame) {
+    CURRENT.store(frame as *mut Frame, core::sync::atomic::Ordering::Relaxed);
+}
+
+#[cfg(feature = "host")]
+fn load_current() -> *const Frame {
+    CURRENT.with(core::cell::Cell::get)
+}
+
+#[cfg(feature = "host")]
+fn store_current(frame: *const Frame) {
+    CURRENT.with(|current| current.set(frame));
+}
@@ -0,0 +1,30 @@
+#![cfg(not(loom))]
+
+use drone_core::crash;
+use std::panic;
+
+#[test]
+fn record_and_take() {
+    crash::clear();
+    assert!(crash::last().is_none());
+    panic::set_hook(Box::new(|info| crash::record(info, Some((3, "worker")))));
+    let (line, result) = (line!(), panic::catch_unwind(|| panic!("boom {}", 42)));
+    let _ = panic::take_hook();
+    assert!(result.is_err());
+    let report = crash::last().unwrap();
+    assert_eq!(report.thread(), Some(3));
+    assert_eq!(report.thread_name(), Some("worker"));
+    assert_eq!(report.message(), "boom 42");
+    assert!(report.file().ends_with("crash.rs"));
+    assert_eq!(report.line(), line);
+    assert_eq!(
+        report.to_string(),
+        format!(
+            "thread 'worker' (#3) panicked at 'boom 42', {}:{line}:{}",
+            report.file(),
+            report.column()
+        )
+    );
+    assert!(crash::take().is_some());
+    assert!(crash::take().is_none());
+}
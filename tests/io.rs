@@ -0,0 +1,105 @@
+#![cfg(not(loom))]
+
+use core::future::Future;
+use core::task::{Context, Poll};
+use drone_core::io::{
+    read_exact, write_all, Cursor, CursorError, Error, ErrorKind, Read, ReadExactError, Seek,
+    SeekFrom, Write, WriteAllError,
+};
+use futures::task::noop_waker_ref;
+
+fn ready<F: Future>(fut: F) -> F::Output {
+    let mut fut = Box::pin(fut);
+    let mut cx = Context::from_waker(noop_waker_ref());
+    match fut.as_mut().poll(&mut cx) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("cursor future is not ready"),
+    }
+}
+
+#[test]
+fn read() {
+    let mut cursor = Cursor::new(vec![1_u8, 2, 3, 4, 5]);
+    let mut buf = [0; 3];
+    assert_eq!(ready(cursor.read(&mut buf)), Ok(3));
+    assert_eq!(buf, [1, 2, 3]);
+    assert_eq!(ready(cursor.read(&mut buf)), Ok(2));
+    assert_eq!(buf[..2], [4, 5]);
+    assert_eq!(ready(cursor.read(&mut buf)), Ok(0));
+    assert_eq!(cursor.position(), 5);
+    assert!(cursor.is_empty());
+}
+
+#[test]
+fn seek() {
+    let mut cursor = Cursor::new([1_u8, 2, 3, 4, 5]);
+    assert_eq!(ready(cursor.seek(SeekFrom::Start(2))), Ok(2));
+    assert_eq!(cursor.remaining_slice(), [3, 4, 5]);
+    assert_eq!(ready(cursor.seek(SeekFrom::Current(-1))), Ok(1));
+    assert_eq!(ready(cursor.seek(SeekFrom::End(-2))), Ok(3));
+    assert_eq!(ready(cursor.seek(SeekFrom::End(2))), Ok(7));
+    assert!(cursor.is_empty());
+    let mut buf = [0; 1];
+    assert_eq!(ready(cursor.read(&mut buf)), Ok(0));
+}
+
+#[test]
+fn seek_errors() {
+    let mut cursor = Cursor::new([1_u8, 2, 3]);
+    assert_eq!(ready(cursor.seek(SeekFrom::End(-4))), Err(CursorError::NegativePosition));
+    assert_eq!(ready(cursor.seek(SeekFrom::Current(-1))), Err(CursorError::NegativePosition));
+    assert_eq!(cursor.position(), 0);
+    assert_eq!(ready(cursor.seek(SeekFrom::Start(u64::MAX))), Ok(u64::MAX));
+    assert_eq!(ready(cursor.seek(SeekFrom::Current(1))), Err(CursorError::Overflow));
+    assert_eq!(cursor.position(), u64::MAX);
+}
+
+#[test]
+fn write_slice() {
+    let mut buf = [0_u8; 4];
+    let mut cursor = Cursor::new(&mut buf[..]);
+    assert_eq!(ready(cursor.write(&[1, 2, 3])), Ok(3));
+    assert_eq!(ready(cursor.write(&[4, 5, 6])), Ok(1));
+    assert_eq!(ready(cursor.write(&[7])), Ok(0));
+    assert_eq!(buf, [1, 2, 3, 4]);
+}
+
+#[test]
+fn write_vec() {
+    let mut cursor = Cursor::new(vec![1_u8, 2, 3]);
+    assert_eq!(ready(cursor.seek(SeekFrom::Start(1))), Ok(1));
+    assert_eq!(ready(cursor.write(&[4, 5, 6])), Ok(3));
+    assert_eq!(cursor.get_ref(), &[1, 4, 5, 6]);
+    assert_eq!(ready(cursor.seek(SeekFrom::End(2))), Ok(6));
+    assert_eq!(ready(cursor.write(&[7])), Ok(1));
+    assert_eq!(cursor.into_inner(), [1, 4, 5, 6, 0, 0, 7]);
+}
+
+#[test]
+fn read_exact_eof() {
+    let mut cursor = Cursor::new([1_u8, 2, 3, 4, 5]);
+    let mut buf = [0; 3];
+    assert_eq!(ready(read_exact(&mut cursor, &mut buf)), Ok(()));
+    assert_eq!(buf, [1, 2, 3]);
+    let err = ready(read_exact(&mut cursor, &mut buf)).unwrap_err();
+    assert_eq!(err, ReadExactError::UnexpectedEof);
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn write_all_zero() {
+    let mut buf = [0_u8; 4];
+    let mut cursor = Cursor::new(&mut buf[..]);
+    assert_eq!(ready(write_all(&mut cursor, &[1, 2])), Ok(()));
+    let err = ready(write_all(&mut cursor, &[3, 4, 5])).unwrap_err();
+    assert_eq!(err, WriteAllError::WriteZero);
+    assert_eq!(err.kind(), ErrorKind::WriteZero);
+    assert_eq!(buf, [1, 2, 3, 4]);
+}
+
+#[test]
+fn error_kind() {
+    assert_eq!(CursorError::NegativePosition.kind(), ErrorKind::InvalidInput);
+    let err = ReadExactError::Other(CursorError::Overflow);
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
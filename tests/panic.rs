@@ -0,0 +1,21 @@
+#![cfg(not(loom))]
+
+use core::alloc::Layout;
+use core::panic::PanicInfo;
+use drone_core::panic;
+
+fn panic_hook(_info: &PanicInfo<'_>) {}
+
+fn alloc_error_hook(_layout: Layout) {}
+
+#[test]
+fn hooks() {
+    assert!(panic::take_hook().is_none());
+    panic::set_hook(panic_hook);
+    assert!(panic::take_hook().is_some());
+    assert!(panic::take_hook().is_none());
+    assert!(panic::take_alloc_error_hook().is_none());
+    panic::set_alloc_error_hook(alloc_error_hook);
+    assert!(panic::take_alloc_error_hook().is_some());
+    assert!(panic::take_alloc_error_hook().is_none());
+}
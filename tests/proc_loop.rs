@@ -0,0 +1,147 @@
+#![cfg(not(loom))]
+
+use drone_core::proc_loop::{check_stack_guard, paint_stack, stack_high_water, STACK_PAINT};
+
+#[test]
+fn high_water() {
+    let mut stack = [0_u32; 16];
+    let bottom = stack.as_mut_ptr().cast::<u8>();
+    unsafe { paint_stack(bottom, 64) };
+    assert!(stack.iter().all(|&word| word == STACK_PAINT));
+    assert_eq!(unsafe { stack_high_water(bottom, 64) }, 0);
+    stack[12..].fill(0);
+    assert_eq!(unsafe { stack_high_water(bottom, 64) }, 16);
+    stack[5] = 0;
+    assert_eq!(unsafe { stack_high_water(bottom, 64) }, 44);
+    stack[0] = 0;
+    assert_eq!(unsafe { stack_high_water(bottom, 64) }, 64);
+}
+
+#[test]
+fn guard() {
+    let mut stack = [0_u32; 16];
+    let bottom = stack.as_mut_ptr().cast::<u8>();
+    unsafe { paint_stack(bottom, 64) };
+    stack[8..].fill(0);
+    assert!(unsafe { check_stack_guard(bottom, 16) });
+    stack[4] = 0;
+    assert!(unsafe { check_stack_guard(bottom, 16) });
+    stack[3] = 0;
+    assert!(!unsafe { check_stack_guard(bottom, 16) });
+    assert!(unsafe { check_stack_guard(bottom, 0) });
+}
+
+#[cfg(feature = "host")]
+mod host {
+    use core::convert::Infallible;
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{Context as TaskContext, Poll};
+    use drone_core::proc_loop;
+    use drone_core::proc_loop::host::{HostContext, HostSess};
+    use drone_core::proc_loop::{Context, ProcLoop, Sess};
+    use futures::task::noop_waker_ref;
+    use std::sync::Mutex;
+
+    static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct Recorder;
+
+    impl ProcLoop for Recorder {
+        type Cmd = u32;
+        type CmdRes = u32;
+        type Context = HostContext<u32, u32>;
+        type Req = u32;
+        type ReqRes = u32;
+
+        const STACK_SIZE: usize = 0;
+
+        fn run_cmd(cmd: u32, context: Self::Context) -> u32 {
+            push(format!("cmd {cmd}"));
+            let a = context.req(cmd);
+            let b = context.req(a);
+            push(format!("res {b}"));
+            b
+        }
+
+        fn on_create() {
+            push("create".into());
+        }
+
+        fn on_enter() {
+            push("enter".into());
+        }
+
+        fn on_drop() {
+            push("drop".into());
+        }
+    }
+
+    struct Fs;
+
+    #[proc_loop]
+    impl ProcLoop for Fs {
+        type Context = HostContext<FsReq, FsReqRes>;
+
+        const STACK_SIZE: usize = 0;
+
+        #[cmd]
+        fn checksum(context: Self::Context, index: u8) -> u32 {
+            context.read_block(index).iter().map(|&byte| u32::from(byte)).sum()
+        }
+
+        #[cmd]
+        fn ping(_context: Self::Context) {}
+
+        #[req]
+        fn read_block(index: u8) -> [u8; 4];
+    }
+
+    struct Storage;
+
+    impl FsReqHandler for Storage {
+        type Error = Infallible;
+
+        fn read_block(
+            &mut self,
+            index: u8,
+        ) -> Pin<Box<dyn Future<Output = Result<[u8; 4], Infallible>> + Send + '_>> {
+            Box::pin(async move { Ok([index; 4]) })
+        }
+    }
+
+    fn push(event: String) {
+        EVENTS.lock().unwrap().push(event);
+    }
+
+    #[test]
+    fn lifecycle() {
+        let mut sess = HostSess::<Recorder, _>::new(|req| {
+            push(format!("req {req}"));
+            req * 10
+        });
+        let mut cx = TaskContext::from_waker(noop_waker_ref());
+        assert!(matches!(sess.cmd(1).as_mut().poll(&mut cx), Poll::Ready(Ok(100))));
+        assert!(matches!(sess.cmd(2).as_mut().poll(&mut cx), Poll::Ready(Ok(200))));
+        assert!(sess.stack_high_water().is_none());
+        drop(sess);
+        assert_eq!(*EVENTS.lock().unwrap(), [
+            "create", "enter", "cmd 1", "req 1", "req 10", "res 100", "cmd 2", "req 2", "req 20",
+            "res 200", "drop"
+        ]);
+    }
+
+    #[test]
+    fn typed() {
+        let mut sess = HostSess::<Fs, _>::new(|req: FsReq| {
+            let mut cx = TaskContext::from_waker(noop_waker_ref());
+            match req.run(&mut Storage).as_mut().poll(&mut cx) {
+                Poll::Ready(Ok(res)) => res,
+                _ => unreachable!(),
+            }
+        });
+        let mut cx = TaskContext::from_waker(noop_waker_ref());
+        assert!(matches!(sess.checksum(3).as_mut().poll(&mut cx), Poll::Ready(Ok(12))));
+        assert!(matches!(sess.ping().as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+    }
+}
@@ -1,13 +1,22 @@
 #![cfg(not(loom))]
 #![no_implicit_prelude]
 
-use ::drone_core::thr;
-use ::drone_core::thr::{pending_size, SoftThrToken, SoftThread, ThrExec, PRIORITY_LEVELS};
+use ::drone_core::thr::{
+    pending_size, Counted, MaxRestarts, RestartCounter, RestartPolicy, SoftThrToken, SoftThread,
+    ThrExec, PRIORITY_LEVELS,
+};
 use ::drone_core::token::Token;
-use ::std::assert_eq;
+use ::drone_core::{task_local, thr};
+use ::futures::future::{poll_fn, ready, Ready};
 use ::std::clone::Clone;
+use ::std::future::Future;
+use ::std::ops::Drop;
+use ::std::option::Option::{self, None, Some};
+use ::std::result::Result::{Err, Ok};
 use ::std::sync::{Arc, Mutex};
+use ::std::task::Poll;
 use ::std::vec::Vec;
+use ::std::{assert, assert_eq};
 
 #[test]
 fn test_set_pending() {
@@ -68,9 +77,9 @@ fn test_pending_size() {
             b28; b29; b30; b31; b32;
         };
     }
-    assert_eq!(pending_size::<Thr0>(), 1 + 0 * PRIORITY_LEVELS as usize);
-    assert_eq!(pending_size::<Thr32>(), 1 + 1 * PRIORITY_LEVELS as usize);
-    assert_eq!(pending_size::<Thr33>(), 1 + 2 * PRIORITY_LEVELS as usize);
+    assert_eq!(pending_size::<Thr0>(), 1 + 0 * PRIORITY_LEVELS as usize + 0 + 1);
+    assert_eq!(pending_size::<Thr32>(), 1 + 1 * PRIORITY_LEVELS as usize + 2 + 1);
+    assert_eq!(pending_size::<Thr33>(), 1 + 2 * PRIORITY_LEVELS as usize + 4 + 1);
 }
 
 #[test]
@@ -113,3 +122,438 @@ fn test_priorities() {
         assert_eq!(cell, 0);
     }
 }
+
+#[test]
+fn test_wakeup_at() {
+    thr::soft! {
+        thread => Thr {};
+        local => ThrLocal {};
+        index => Thrs;
+        threads => { thr_0; thr_1; thr_2; };
+    }
+    let Thrs { thr_0, thr_1, thr_2 } = unsafe { Thrs::take() };
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log_0 = Arc::clone(&log);
+    let log_1 = Arc::clone(&log);
+    let log_2 = Arc::clone(&log);
+    thr_0.set_priority(1);
+    thr_1.set_priority(0);
+    thr_2.set_priority(0);
+    thr_0.add_exec(async move {
+        log_0.lock().unwrap().push(0);
+        thr_1.wakeup();
+        thr_2.wakeup_at(2);
+        log_0.lock().unwrap().push(1);
+    });
+    thr_1.add_exec(async move {
+        log_1.lock().unwrap().push(2);
+    });
+    thr_2.add_exec(async move {
+        log_2.lock().unwrap().push(3);
+    });
+    thr_0.wakeup();
+    assert_eq!(*log.lock().unwrap(), &[0, 3, 1, 2]);
+    assert_eq!(thr_2.priority(), 0);
+    assert!(!thr_2.is_pending());
+}
+
+#[test]
+fn test_wide_priorities() {
+    thr::soft! {
+        thread => Thr {};
+        local => ThrLocal {};
+        index => Thrs;
+        threads => { thr_0; thr_1; thr_2; };
+        priority_levels => 100;
+    }
+    assert_eq!(pending_size::<Thr>(), 1 + 4 + 100 + 2 + 1);
+    let Thrs { thr_0, thr_1, thr_2 } = unsafe { Thrs::take() };
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log_0 = Arc::clone(&log);
+    let log_1 = Arc::clone(&log);
+    let log_2 = Arc::clone(&log);
+    thr_0.set_priority(3);
+    thr_1.set_priority(40);
+    thr_2.set_priority(99);
+    thr_0.add_exec(async move {
+        log_0.lock().unwrap().push(0);
+        thr_2.wakeup();
+        log_0.lock().unwrap().push(1);
+    });
+    thr_1.add_exec(async move {
+        log_1.lock().unwrap().push(2);
+    });
+    thr_2.add_exec(async move {
+        log_2.lock().unwrap().push(3);
+        thr_1.wakeup();
+        log_2.lock().unwrap().push(4);
+    });
+    thr_0.wakeup();
+    assert_eq!(*log.lock().unwrap(), &[0, 3, 4, 2, 1]);
+    for i in 0..pending_size::<Thr>() {
+        let cell = unsafe { &*Thr::pending().add(i) };
+        #[cfg(feature = "atomics")]
+        let cell = cell.load(::std::sync::atomic::Ordering::Relaxed);
+        #[cfg(not(feature = "atomics"))]
+        let cell = cell.load();
+        assert_eq!(cell, 0);
+    }
+}
+
+#[test]
+fn test_round_robin() {
+    thr::soft! {
+        thread => Thr {};
+        local => ThrLocal {};
+        index => Thrs;
+        threads => { thr_0; thr_1; thr_2; };
+        round_robin => true;
+    }
+    fn logger(log: &Arc<Mutex<Vec<u16>>>, thr_idx: u16) -> impl Future<Output = ()> {
+        let log = Arc::clone(log);
+        poll_fn(move |_| {
+            log.lock().unwrap().push(thr_idx);
+            Poll::Pending
+        })
+    }
+    assert_eq!(pending_size::<Thr>(), 1 + 2 * PRIORITY_LEVELS as usize + 2 + 1);
+    let Thrs { thr_0, thr_1, thr_2 } = unsafe { Thrs::take() };
+    let log = Arc::new(Mutex::new(Vec::new()));
+    thr_0.add_exec(logger(&log, 0));
+    thr_1.add_exec(logger(&log, 1));
+    thr_2.add_exec(logger(&log, 2));
+    for _ in 0..3 {
+        unsafe {
+            Thr::will_preempt(2);
+            Thr::will_preempt(0);
+            Thr::will_preempt(1);
+        }
+        Thr::preempt();
+    }
+    assert_eq!(*log.lock().unwrap(), &[0, 1, 2, 1, 2, 0, 2, 0, 1]);
+}
+
+#[test]
+fn test_idle() {
+    thr::soft! {
+        thread => Thr {};
+        local => ThrLocal {};
+        index => Thrs;
+        threads => { thr_0; thr_1; };
+        idle => idle;
+    }
+    static LOG: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+    fn idle() {
+        LOG.lock().unwrap().push(3);
+    }
+    let Thrs { thr_0, thr_1 } = unsafe { Thrs::take() };
+    thr_0.set_priority(0);
+    thr_1.set_priority(1);
+    thr_0.add_exec(async move {
+        LOG.lock().unwrap().push(0);
+        thr_1.wakeup();
+        LOG.lock().unwrap().push(1);
+    });
+    thr_1.add_exec(async move {
+        LOG.lock().unwrap().push(2);
+    });
+    assert_eq!(Thr::idle_count(), 0);
+    thr_0.wakeup();
+    assert_eq!(*LOG.lock().unwrap(), &[0, 2, 1]);
+    assert_eq!(Thr::idle_count(), 0);
+    Thr::run_idle();
+    assert_eq!(*LOG.lock().unwrap(), &[0, 2, 1, 3]);
+    assert_eq!(Thr::idle_count(), 1);
+    Thr::run_idle();
+    assert_eq!(*LOG.lock().unwrap(), &[0, 2, 1, 3, 3]);
+    assert_eq!(Thr::idle_count(), 2);
+}
+
+#[test]
+fn test_task_local() {
+    thr::soft! {
+        thread => Thr {};
+        local => ThrLocal {};
+        index => Thrs;
+        threads => { thr_0; thr_1; };
+    }
+    task_local! {
+        static ID: u8;
+    }
+    struct Probe(Arc<Mutex<Vec<u8>>>);
+    impl Drop for Probe {
+        fn drop(&mut self) {
+            self.0.lock().unwrap().push(ID.try_with(|id| *id).unwrap_or(0));
+        }
+    }
+    fn yield_once() -> impl Future<Output = ()> {
+        let mut yielded = false;
+        poll_fn(move |_| {
+            if yielded {
+                return Poll::Ready(());
+            }
+            yielded = true;
+            Poll::Pending
+        })
+    }
+    let Thrs { thr_0, thr_1 } = unsafe { Thrs::take() };
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log_a = Arc::clone(&log);
+    let log_b = Arc::clone(&log);
+    let log_1 = Arc::clone(&log);
+    thr_0.set_priority(0);
+    thr_1.set_priority(1);
+    thr_0.add_exec(ID.scope(1, async move {
+        log_a.lock().unwrap().push(ID.with(|id| *id));
+        thr_1.wakeup();
+        yield_once().await;
+        log_a.lock().unwrap().push(ID.with(|id| *id));
+    }));
+    thr_0.add_exec(ID.scope(2, async move {
+        log_b.lock().unwrap().push(ID.with(|id| *id));
+        ID.scope(3, async {
+            log_b.lock().unwrap().push(ID.with(|id| *id));
+            yield_once().await;
+            log_b.lock().unwrap().push(ID.with(|id| *id));
+        })
+        .await;
+        log_b.lock().unwrap().push(ID.with(|id| *id));
+    }));
+    thr_1.add_exec(async move {
+        log_1.lock().unwrap().push(ID.try_with(|id| *id).unwrap_or(0));
+    });
+    thr_0.wakeup();
+    assert_eq!(*log.lock().unwrap(), &[1, 0, 2, 3]);
+    thr_0.wakeup();
+    assert_eq!(*log.lock().unwrap(), &[1, 0, 2, 3, 1, 3, 2]);
+    let probe = Probe(Arc::clone(&log));
+    ::std::mem::drop(ID.scope(4, async move {
+        let _probe = probe;
+    }));
+    assert_eq!(*log.lock().unwrap(), &[1, 0, 2, 3, 1, 3, 2, 4]);
+    assert_eq!(ID.try_with(|id| *id), None);
+}
+
+#[test]
+fn test_exec_with_handler() {
+    thr::soft! {
+        thread => Thr {};
+        local => ThrLocal {};
+        index => Thrs;
+        threads => { thr_0; };
+    }
+    let Thrs { thr_0 } = unsafe { Thrs::take() };
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log_ok = Arc::clone(&log);
+    let log_err = Arc::clone(&log);
+    thr_0.exec_with_handler(async { Ok::<(), u8>(()) }, move |err| {
+        log_ok.lock().unwrap().push(err);
+    });
+    thr_0.exec_with_handler(async { Err(1) }, move |err| {
+        log_err.lock().unwrap().push(err);
+    });
+    assert_eq!(*log.lock().unwrap(), &[1]);
+}
+
+#[test]
+fn test_supervise() {
+    thr::soft! {
+        thread => Thr {};
+        local => ThrLocal {};
+        index => Thrs;
+        threads => { thr_0; };
+    }
+    struct Policy(Arc<Mutex<Vec<(u8, u32)>>>);
+    impl RestartPolicy<u8> for Policy {
+        type Delay = Ready<()>;
+
+        fn restart(&mut self, err: u8, restarts: u32) -> Option<Ready<()>> {
+            self.0.lock().unwrap().push((err, restarts));
+            Some(ready(()))
+        }
+    }
+    let Thrs { thr_0 } = unsafe { Thrs::take() };
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut attempts = 0;
+    thr_0.supervise(
+        move || {
+            attempts += 1;
+            let attempt = attempts;
+            async move { if attempt < 3 { Err(attempt) } else { Ok(()) } }
+        },
+        Policy(Arc::clone(&log)),
+    );
+    assert_eq!(*log.lock().unwrap(), &[(1, 0), (2, 1)]);
+    let attempts = Arc::new(Mutex::new(0));
+    let attempts_0 = Arc::clone(&attempts);
+    thr_0.supervise(
+        move || {
+            *attempts_0.lock().unwrap() += 1;
+            async { Err(()) }
+        },
+        MaxRestarts(2),
+    );
+    assert_eq!(*attempts.lock().unwrap(), 3);
+    static RESTARTS: RestartCounter = RestartCounter::new();
+    thr_0.supervise(|| async { Err(()) }, Counted::new(MaxRestarts(2), &RESTARTS));
+    assert_eq!(RESTARTS.get(), 2);
+}
+
+#[cfg(feature = "host")]
+mod host {
+    use ::drone_core::fib::{FiberState, ThrFiberClosure};
+    use ::drone_core::thr;
+    use ::drone_core::thr::sim::{Event, Sim, Step};
+    use ::drone_core::thr::{SoftThrToken, ThrExec};
+    use ::drone_core::token::Token;
+    use ::futures::future::pending;
+    use ::std::iter::Iterator;
+    use ::std::vec::Vec;
+    use ::std::{assert, assert_eq, panic};
+
+    #[test]
+    fn test_sim_scripted() {
+        thr::soft! {
+            thread => Thr {};
+            local => ThrLocal {};
+            index => Thrs;
+            threads => { thr_0; thr_1; thr_2; };
+            resume => thr::sim::resume;
+        }
+        let Thrs { thr_0, thr_1, thr_2 } = unsafe { Thrs::take() };
+        thr_0.set_priority(0);
+        thr_1.set_priority(1);
+        thr_2.set_priority(2);
+        thr_0.add_exec(pending());
+        thr_1.add_exec(pending());
+        thr_2.add_exec(pending());
+        let events = [Event::Pend(0), Event::Pend(2), Event::Pend(1), Event::Pend(0)];
+        let trace = Sim::<Thr>::new(events).run();
+        assert_eq!(trace.steps(), &[
+            Step::Event(Event::Pend(0)),
+            Step::Enter(0),
+            Step::Event(Event::Pend(2)),
+            Step::Enter(2),
+            Step::Event(Event::Pend(1)),
+            Step::Exit(2),
+            Step::Enter(1),
+            Step::Event(Event::Pend(0)),
+            Step::Exit(1),
+            Step::Exit(0),
+            Step::Enter(0),
+            Step::Exit(0),
+        ]);
+        assert_eq!(trace.runs(), &[0, 2, 1, 0]);
+        assert_eq!(trace.preemptions(), &[(0, 2), (0, 1)]);
+    }
+
+    #[test]
+    fn test_sim_fibers() {
+        thr::soft! {
+            thread => Thr {};
+            local => ThrLocal {};
+            index => Thrs;
+            threads => { thr_0; thr_1; thr_2; };
+            resume => thr::sim::resume;
+        }
+        let Thrs { thr_0, thr_1, thr_2 } = unsafe { Thrs::take() };
+        thr_0.set_priority(0);
+        thr_1.set_priority(1);
+        thr_2.set_priority(2);
+        thr_0.add_exec(pending());
+        thr_0.add_exec(pending());
+        thr_2.add_exec(pending());
+        let events = [Event::Pend(0), Event::Pend(1), Event::Pend(2)];
+        let trace = Sim::<Thr>::new(events).run();
+        assert_eq!(trace.runs(), &[0, 1, 2]);
+        assert_eq!(trace.preemptions(), &[(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn test_sim_panic() {
+        thr::soft! {
+            thread => Thr {};
+            local => ThrLocal {};
+            index => Thrs;
+            threads => { thr_0; };
+            resume => thr::sim::resume;
+        }
+        let Thrs { thr_0 } = unsafe { Thrs::take() };
+        thr_0.add_fn(|| -> FiberState<(), ()> { panic!("boom") });
+        let result = panic::catch_unwind(|| Sim::<Thr>::new([Event::Pend(0)]).run());
+        assert!(result.is_err());
+        assert_eq!(Sim::<Thr>::new([]).run().steps(), &[]);
+    }
+
+    #[test]
+    fn test_sim_boosted() {
+        thr::soft! {
+            thread => Thr {};
+            local => ThrLocal {};
+            index => Thrs;
+            threads => { thr_0; thr_1; };
+            resume => thr::sim::resume;
+        }
+        let Thrs { thr_0, thr_1 } = unsafe { Thrs::take() };
+        thr_0.set_priority(0);
+        thr_1.set_priority(1);
+        thr_0.add_exec(pending());
+        thr_1.add_exec(pending());
+        let events = [Event::Pend(1), Event::PendAt(0, 2), Event::PendAt(0, 2)];
+        let trace = Sim::<Thr>::new(events).run();
+        assert_eq!(trace.runs(), &[1, 0, 0]);
+        assert_eq!(trace.preemptions(), &[(1, 0)]);
+    }
+
+    #[test]
+    fn test_sim_boosted_running() {
+        thr::soft! {
+            thread => Thr {};
+            local => ThrLocal {};
+            index => Thrs;
+            threads => { thr_0; };
+            resume => thr::sim::resume;
+        }
+        let Thrs { thr_0 } = unsafe { Thrs::take() };
+        thr_0.set_priority(0);
+        thr_0.add_exec(pending());
+        let trace = Sim::<Thr>::new([Event::Pend(0), Event::PendAt(0, 2)]).run();
+        assert_eq!(trace.steps(), &[
+            Step::Event(Event::Pend(0)),
+            Step::Enter(0),
+            Step::Event(Event::PendAt(0, 2)),
+            Step::Exit(0),
+            Step::Enter(0),
+            Step::Exit(0),
+        ]);
+        assert_eq!(trace.preemptions(), &[]);
+    }
+
+    #[test]
+    fn test_sim_seeded() {
+        thr::soft! {
+            thread => Thr {};
+            local => ThrLocal {};
+            index => Thrs;
+            threads => { thr_0; thr_1; thr_2; thr_3; };
+            resume => thr::sim::resume;
+        }
+        let Thrs { thr_0, thr_1, thr_2, thr_3 } = unsafe { Thrs::take() };
+        thr_0.set_priority(0);
+        thr_1.set_priority(1);
+        thr_2.set_priority(2);
+        thr_3.set_priority(3);
+        thr_0.add_exec(pending());
+        thr_1.add_exec(pending());
+        thr_2.add_exec(pending());
+        thr_3.add_exec(pending());
+        let events = Sim::<Thr>::seeded(7, 64).events().copied().collect::<Vec<_>>();
+        assert_eq!(events.len(), 64);
+        assert_eq!(events, Sim::<Thr>::seeded(7, 64).events().copied().collect::<Vec<_>>());
+        let trace = Sim::<Thr>::seeded(7, 64).run();
+        assert_eq!(trace, Sim::<Thr>::seeded(7, 64).run());
+        for (preempted, preempting) in trace.preemptions() {
+            assert!(preempting > preempted);
+        }
+    }
+}
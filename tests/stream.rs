@@ -22,6 +22,10 @@ init-primary = true
 [stream.core1]
 ram = "main"
 size = "260"
+
+[stream.core2]
+ram = "main"
+size = "260"
 "# }
 
 stream! {
@@ -40,3 +44,256 @@ stream! {
     metadata => pub Stream1;
     instance => pub STREAM1;
 }
+
+stream! {
+    layout => core2;
+    metadata => pub Stream2;
+    instance => pub STREAM2;
+    transport => rtt(up: 2, down: 1);
+}
+
+#[test]
+fn rtt_layout() {
+    ::std::assert!(::core::mem::size_of::<Stream2>() <= 260);
+    ::std::assert_eq!(::core::mem::size_of_val(&Stream2::zeroed().down_buffers), 16);
+}
+
+#[cfg(feature = "host")]
+mod host {
+    use ::drone_core::io::Read;
+    use ::drone_core::stream::{host, Stream, StreamRecord};
+    use ::drone_core::{print, println, stream};
+    use ::std::clone::Clone;
+    use ::std::future::Future;
+    use ::std::iter::Iterator;
+    use ::std::marker::Copy;
+    use ::std::pin::Pin;
+    use ::std::sync::atomic::{AtomicUsize, Ordering};
+    use ::std::sync::Mutex;
+    use ::std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+    use ::std::vec::Vec;
+    use ::std::{assert, assert_eq, matches, vec};
+
+    static WAKE_COUNT: AtomicUsize = AtomicUsize::new(0);
+    static WRITERS_LOCK: Mutex<()> = Mutex::new(());
+    static LEVELS_LOCK: Mutex<()> = Mutex::new(());
+    static STDIN_LOCK: Mutex<()> = Mutex::new(());
+
+    fn counting_waker() -> Waker {
+        unsafe fn clone(data: *const ()) -> RawWaker {
+            RawWaker::new(data, &VTABLE)
+        }
+        unsafe fn wake(_data: *const ()) {
+            WAKE_COUNT.fetch_add(1, Ordering::SeqCst);
+        }
+        unsafe fn drop(_data: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+        unsafe { Waker::from_raw(RawWaker::new(::std::ptr::null(), &VTABLE)) }
+    }
+
+    #[test]
+    fn print() {
+        host::enable(0);
+        print!("hello ");
+        println!("{}", 42);
+        assert_eq!(host::take_string(0), "hello 42\n");
+        assert_eq!(host::take_string(0), "");
+    }
+
+    #[test]
+    fn transactions() {
+        host::enable(7);
+        Stream::new(7).write(0x1234_u16).write_transaction(b"abc");
+        assert_eq!(host::take_transactions(7), [vec![0x34, 0x12], vec![b'a', b'b', b'c']]);
+    }
+
+    #[derive(Clone, Copy, StreamRecord)]
+    #[repr(C)]
+    struct Sample {
+        timestamp: u32,
+        voltage: f32,
+        current: [i16; 2],
+    }
+
+    #[test]
+    fn records() {
+        host::enable(13);
+        let sample = Sample { timestamp: 0x0102_0304, voltage: 1.5, current: [-1, 2] };
+        Stream::new(13)
+            .write(-2_i8)
+            .write(0x0102_0304_0506_0708_u64)
+            .write([1.0_f64])
+            .write(sample);
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&sample.timestamp.to_ne_bytes());
+        expected.extend_from_slice(&sample.voltage.to_ne_bytes());
+        expected.extend_from_slice(&sample.current[0].to_ne_bytes());
+        expected.extend_from_slice(&sample.current[1].to_ne_bytes());
+        assert_eq!(host::take_transactions(13), [
+            vec![0xFE],
+            0x0102_0304_0506_0708_u64.to_ne_bytes().to_vec(),
+            1.0_f64.to_ne_bytes().to_vec(),
+            expected,
+        ]);
+    }
+
+    #[test]
+    fn wrap() {
+        host::enable(8);
+        let data = (0..=255).cycle().take(5000).collect::<Vec<u8>>();
+        Stream::new(8).write_bytes(&data);
+        assert_eq!(host::take_bytes(8), data);
+    }
+
+    #[test]
+    fn disabled() {
+        host::disable(9);
+        stream::error!(stream: 9, "nothing");
+        assert_eq!(host::take_transactions(9).len(), 0);
+    }
+
+    #[test]
+    fn levels() {
+        let _lock = LEVELS_LOCK.lock().unwrap();
+        host::enable(10);
+        stream::error!(stream: 10, "boom {}", 1);
+        stream::debug!(stream: 10, "hidden");
+        Stream::new(10).set_level(stream::Level::Debug);
+        stream::debug!(stream: 10, "shown");
+        assert_eq!(
+            host::take_string(10),
+            "[ERROR stream::host] boom 1\n[DEBUG stream::host] shown\n"
+        );
+    }
+
+    #[test]
+    fn module_levels() {
+        let _lock = LEVELS_LOCK.lock().unwrap();
+        host::enable(14);
+        stream::set_module_levels(&[
+            ("stream", stream::Level::Error),
+            ("stream::host", stream::Level::Trace),
+        ]);
+        stream::trace!(stream: 14, "shown");
+        stream::set_module_levels(&[
+            ("stream", stream::Level::Error),
+            ("stream::hos", stream::Level::Trace),
+        ]);
+        stream::warn!(stream: 14, "hidden");
+        stream::set_module_levels(&[]);
+        assert_eq!(host::take_string(14), "[TRACE stream::host] shown\n");
+    }
+
+    #[test]
+    fn write_async() {
+        let _lock = WRITERS_LOCK.lock().unwrap();
+        host::enable(11);
+        let filler = [0xAA; 100];
+        while Stream::new(11).try_write_transaction(&filler) {}
+        let data = (0..200).collect::<Vec<u8>>();
+        let waker = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut writer = Stream::new(11).write_async(&data);
+        assert!(matches!(Pin::new(&mut writer).poll(&mut cx), Poll::Pending));
+        let filled = host::take_bytes(11);
+        assert!(filled.len() > 800);
+        assert!(filled.iter().all(|&byte| byte == 0xAA));
+        let wake_count = WAKE_COUNT.load(Ordering::SeqCst);
+        stream::poll_writers();
+        assert!(WAKE_COUNT.load(Ordering::SeqCst) > wake_count);
+        assert!(matches!(Pin::new(&mut writer).poll(&mut cx), Poll::Ready(())));
+        assert_eq!(host::take_bytes(11), data);
+    }
+
+    #[test]
+    fn write_async_registers_once() {
+        static WAKES: AtomicUsize = AtomicUsize::new(0);
+        unsafe fn clone(data: *const ()) -> RawWaker {
+            RawWaker::new(data, &VTABLE)
+        }
+        unsafe fn wake(_data: *const ()) {
+            WAKES.fetch_add(1, Ordering::SeqCst);
+        }
+        unsafe fn drop(_data: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+        let _lock = WRITERS_LOCK.lock().unwrap();
+        host::enable(15);
+        while Stream::new(15).try_write_transaction(&[0xAA; 100]) {}
+        let waker = unsafe { Waker::from_raw(RawWaker::new(::std::ptr::null(), &VTABLE)) };
+        let mut cx = Context::from_waker(&waker);
+        let data = [0x55; 8];
+        let mut writer = Stream::new(15).write_async(&data);
+        for _ in 0..3 {
+            assert!(matches!(Pin::new(&mut writer).poll(&mut cx), Poll::Pending));
+        }
+        stream::poll_writers();
+        assert_eq!(WAKES.load(Ordering::SeqCst), 1);
+        assert!(matches!(Pin::new(&mut writer).poll(&mut cx), Poll::Pending));
+        ::std::mem::drop(writer);
+        stream::poll_writers();
+        assert_eq!(WAKES.load(Ordering::SeqCst), 1);
+        host::take_bytes(15);
+    }
+
+    #[test]
+    fn try_write_drops() {
+        host::enable(12);
+        let mut written = 0;
+        while Stream::new(12).try_write_transaction(b"0123456789") {
+            written += 1;
+        }
+        assert!(!Stream::new(12).try_write_transaction(b"dropped"));
+        assert_eq!(host::take_transactions(12).len(), written);
+        assert!(Stream::new(12).try_write_transaction(b"kept"));
+        assert_eq!(host::take_transactions(12), [b"kept".to_vec()]);
+    }
+
+    #[test]
+    fn stdin() {
+        let _lock = STDIN_LOCK.lock().unwrap();
+        let mut stdin = stream::stdin();
+        let mut buf = [0; 4];
+        let waker = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut read = stdin.read(&mut buf);
+        assert!(matches!(read.as_mut().poll(&mut cx), Poll::Pending));
+        assert_eq!(host::send(b"hello"), 5);
+        stream::poll_stdin();
+        assert!(matches!(read.as_mut().poll(&mut cx), Poll::Ready(Ok(4))));
+        ::std::mem::drop(read);
+        assert_eq!(&buf, b"hell");
+        let mut rest = [0; 4];
+        let mut read = stdin.read(&mut rest);
+        assert!(matches!(read.as_mut().poll(&mut cx), Poll::Ready(Ok(1))));
+        ::std::mem::drop(read);
+        assert_eq!(rest[0], b'o');
+    }
+
+    #[test]
+    fn stdin_registers_once() {
+        static WAKES: AtomicUsize = AtomicUsize::new(0);
+        unsafe fn clone(data: *const ()) -> RawWaker {
+            RawWaker::new(data, &VTABLE)
+        }
+        unsafe fn wake(_data: *const ()) {
+            WAKES.fetch_add(1, Ordering::SeqCst);
+        }
+        unsafe fn drop(_data: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+        let _lock = STDIN_LOCK.lock().unwrap();
+        let waker = unsafe { Waker::from_raw(RawWaker::new(::std::ptr::null(), &VTABLE)) };
+        let mut cx = Context::from_waker(&waker);
+        let mut stdin = stream::stdin();
+        let mut buf = [0; 4];
+        let mut read = stdin.read(&mut buf);
+        for _ in 0..3 {
+            assert!(matches!(read.as_mut().poll(&mut cx), Poll::Pending));
+        }
+        stream::poll_stdin();
+        assert_eq!(WAKES.load(Ordering::SeqCst), 1);
+        assert!(matches!(read.as_mut().poll(&mut cx), Poll::Pending));
+        ::std::mem::drop(read);
+        stream::poll_stdin();
+        assert_eq!(WAKES.load(Ordering::SeqCst), 1);
+    }
+}
@@ -9,13 +9,15 @@ mod t {
     use ::drone_core::thr::prelude::*;
     use ::drone_core::thr::Thread;
     use ::drone_core::token::Token;
-    use ::drone_core::{fib, thr};
-    use ::std::assert_eq;
+    use ::drone_core::{fib, panic, thr};
     use ::std::clone::Clone;
+    use ::std::iter::{ExactSizeIterator, Iterator};
     use ::std::ops::Drop;
+    use ::std::option::Option::{None, Some};
     use ::std::sync::atomic::AtomicI8;
     use ::std::sync::atomic::Ordering::*;
     use ::std::sync::Arc;
+    use ::std::{assert, assert_eq};
 
     thr::pool! {
         /// Test doc attribute
@@ -37,7 +39,11 @@ mod t {
         index => Thrs;
 
         threads => {
+            /// The first thread.
             thr0;
+            /// The second thread.
+            ///
+            /// With a long description.
             thr1;
             thr2;
         }
@@ -111,4 +117,33 @@ mod t {
             assert_eq!(counter.load(Relaxed), -2);
         }
     }
+
+    #[test]
+    fn names() {
+        assert_eq!(Thr::name(0), "thr0");
+        assert_eq!(Thr::name(2), "thr2");
+        assert_eq!(Thr::current_index(), None);
+        panic::register_threads::<Thr>();
+        assert_eq!(panic::current_thread(), None);
+        unsafe {
+            Thr::call(1, |_| {
+                assert_eq!(Thr::current_index(), Some(1));
+                assert_eq!(panic::current_thread(), Some((1, "thr1")));
+            });
+        }
+        assert_eq!(Thr::current_index(), None);
+    }
+
+    #[test]
+    fn iter() {
+        let mut iter = Thr::iter();
+        assert_eq!(iter.len(), 3);
+        let thr0 = iter.next().unwrap();
+        assert_eq!((thr0.index, thr0.name, thr0.doc), (0, "thr0", "The first thread."));
+        let thr1 = iter.next().unwrap();
+        assert_eq!(thr1.doc, "The second thread.\n\nWith a long description.");
+        let thr2 = iter.next().unwrap();
+        assert_eq!((thr2.index, thr2.name, thr2.doc), (2, "thr2", ""));
+        assert!(iter.next().is_none());
+    }
 }